@@ -1,9 +1,10 @@
-use hyper::{Server, StatusCode};
+use hyper::StatusCode;
 use routerman::{
     method::get,
     request::Request,
     router::{Router, RouterBuilder},
 };
+use std::{net::SocketAddr, time::Duration};
 
 fn router() -> RouterBuilder {
     Router::builder()
@@ -15,11 +16,17 @@ fn router() -> RouterBuilder {
 async fn main() {
     let router = router().build();
 
-    let addr = &([127, 0, 0, 1], 8080).into();
-    let server = Server::bind(addr).serve(router);
+    let addr = SocketAddr::from(([127, 0, 0, 1], 8080));
+    let server = routerman::serve(router)
+        .bind(addr)
+        .drain_timeout(Duration::from_secs(10));
 
     println!("Server listening on {}", addr);
-    if let Err(err) = server.await {
-        eprintln!("Error: {}", err);
+    match server.run().await {
+        Ok(report) if report.timed_out() => {
+            eprintln!("Dropped {} requests on shutdown", report.in_flight())
+        }
+        Ok(_) => {}
+        Err(err) => eprintln!("Error: {}", err),
     }
 }
@@ -0,0 +1,134 @@
+//! Procedural macros for routerman. These are re-exported by routerman when its `macros` feature
+//! is enabled, and should be used through it.
+
+use proc_macro::TokenStream;
+use syn::{parse_macro_input, DeriveInput};
+
+mod reply;
+mod route;
+
+/// Route path tokenizer, shared with routerman
+#[path = "../../src/pattern.rs"]
+mod pattern;
+
+macro_rules! route_attributes {
+    ($($(#[$attr:meta])* $name:ident -> $method:literal)*) => {
+        $(
+            #[doc = concat!("Declare an async function as the `", $method, "` handler of a route.")]
+            $(#[$attr])*
+            #[proc_macro_attribute]
+            pub fn $name(attr: TokenStream, item: TokenStream) -> TokenStream {
+                route::expand(stringify!($name), attr.into(), item.into())
+                    .unwrap_or_else(syn::Error::into_compile_error)
+                    .into()
+            }
+        )*
+    };
+}
+
+route_attributes! {
+    ///
+    /// The function is replaced by a function with the same name that takes no arguments and
+    /// returns a `RouterBuilder` containing the route, which can then be merged into another
+    /// router using any formatter. Handler arguments named after a parameter of the path are extracted from that
+    /// parameter, while any other arguments are used as extractors.
+    ///
+    /// The path is validated at compile time, and every parameter must be bound by an argument of
+    /// the same name.
+    /// ```
+    /// # use routerman::{get, post, json::Json, router::Router};
+    /// #[get("/users/:id/posts/:post")]
+    /// async fn show_post(id: u32, post: String) -> String {
+    ///     format!("Post {} of user {}", post, id)
+    /// }
+    ///
+    /// #[post("/echo")]
+    /// async fn echo(Json(body): Json<Vec<String>>) -> Json<Vec<String>> {
+    ///     Json(body)
+    /// }
+    ///
+    /// let router: Router = Router::builder().merge(show_post()).merge(echo()).build();
+    /// ```
+    ///
+    /// Parameters that are not bound by an argument are rejected:
+    /// ```compile_fail
+    /// # use routerman::get;
+    /// #[get("/users/:id")]
+    /// async fn show_user(user_id: u32) -> String {
+    ///     format!("User {}", user_id)
+    /// }
+    /// ```
+    get -> "GET"
+    /// See [`macro@get`].
+    post -> "POST"
+    /// See [`macro@get`].
+    put -> "PUT"
+    /// See [`macro@get`].
+    delete -> "DELETE"
+    /// See [`macro@get`].
+    head -> "HEAD"
+    /// See [`macro@get`].
+    options -> "OPTIONS"
+    /// See [`macro@get`].
+    connect -> "CONNECT"
+    /// See [`macro@get`].
+    patch -> "PATCH"
+    /// See [`macro@get`].
+    trace -> "TRACE"
+}
+
+/// Derive [`Reply`] for an error type. Errors are replied through the formatter's
+/// [`format_error`], with a status code and optional headers added on top, so they are supported
+/// by any formatter.
+///
+/// Attributes can be placed on the type, applying to every variant, or on individual variants:
+/// - `#[reply(status = 404)]`: The status code of the reply. Defaults to `500`.
+/// - `#[reply(header("retry-after", "120"))]`: A header to add to the reply. Can be repeated.
+/// - `#[reply(expose)]`: Format the error itself, exposing its message (its [`Display`] output).
+///   Otherwise, the status code's canonical reason is formatted instead, since messages might
+///   contain sensitive information. A variant can opt out of its type's `expose` with
+///   `#[reply(expose = false)]`.
+/// ```
+/// # use routerman::{method::get, response::Reply, router::Router};
+/// #[derive(Debug, thiserror::Error, Reply)]
+/// enum UserError {
+///     #[error("user `{0}` not found")]
+///     #[reply(status = 404, expose)]
+///     NotFound(String),
+///
+///     #[error("too many requests")]
+///     #[reply(status = 429, header("retry-after", "120"))]
+///     RateLimited,
+///
+///     #[error("database error: {0}")]
+///     Database(String),
+/// }
+///
+/// #[derive(Debug, thiserror::Error, Reply)]
+/// #[reply(status = 400, expose)]
+/// enum UploadError {
+///     #[error("file too large")]
+///     TooLarge,
+///
+///     #[error("failed to store `{0}`")]
+///     #[reply(status = 500, expose = false)]
+///     Storage(String),
+/// }
+///
+/// let router: Router = Router::builder()
+///     .route(
+///         "/users/:id",
+///         get(|| async { Err::<String, _>(UserError::NotFound("ferris".into())) }),
+///     )
+///     .build();
+/// ```
+///
+/// [`Reply`]: https://docs.rs/routerman/latest/routerman/response/trait.Reply.html
+/// [`format_error`]: https://docs.rs/routerman/latest/routerman/response/trait.Formatter.html#method.format_error
+/// [`Display`]: std::fmt::Display
+#[proc_macro_derive(Reply, attributes(reply))]
+pub fn derive_reply(item: TokenStream) -> TokenStream {
+    reply::expand(parse_macro_input!(item as DeriveInput))
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
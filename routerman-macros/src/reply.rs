@@ -0,0 +1,184 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{
+    parenthesized, parse_quote, spanned::Spanned, Attribute, Data, DeriveInput, Error, Fields,
+    LitBool, LitInt, LitStr, Result, Token,
+};
+
+/// Reply configuration of a type or variant
+#[derive(Default, Clone)]
+struct ReplyAttrs {
+    status: Option<u16>,
+    headers: Vec<(LitStr, LitStr)>,
+    expose: Option<bool>,
+}
+
+impl ReplyAttrs {
+    fn parse(attrs: &[Attribute]) -> Result<Self> {
+        let mut parsed = Self::default();
+        for attr in attrs.iter().filter(|attr| attr.path().is_ident("reply")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("status") {
+                    let lit = meta.value()?.parse::<LitInt>()?;
+                    let status = lit.base10_parse::<u16>()?;
+                    if !(100..1000).contains(&status) {
+                        return Err(Error::new(lit.span(), "invalid status code"));
+                    }
+                    parsed.status = Some(status);
+                    Ok(())
+                } else if meta.path.is_ident("header") {
+                    let content;
+                    parenthesized!(content in meta.input);
+                    let name = content.parse::<LitStr>()?;
+                    content.parse::<Token![,]>()?;
+                    let value = content.parse::<LitStr>()?;
+
+                    // Checked here, since invalid headers would only panic once replied
+                    if !is_header_name(&name.value()) {
+                        return Err(Error::new(
+                            name.span(),
+                            "invalid header name (header names must be lowercase)",
+                        ));
+                    }
+                    if !is_header_value(&value.value()) {
+                        return Err(Error::new(value.span(), "invalid header value"));
+                    }
+                    parsed.headers.push((name, value));
+                    Ok(())
+                } else if meta.path.is_ident("expose") {
+                    parsed.expose = match meta.input.peek(Token![=]) {
+                        true => Some(meta.value()?.parse::<LitBool>()?.value),
+                        false => Some(true),
+                    };
+                    Ok(())
+                } else {
+                    Err(meta.error("expected `status`, `header` or `expose`"))
+                }
+            })?;
+        }
+        Ok(parsed)
+    }
+
+    /// Apply the configuration of a variant on top of the configuration of its type
+    fn extend(&self, variant: ReplyAttrs) -> Self {
+        let mut headers = self.headers.clone();
+        headers.extend(variant.headers);
+        Self {
+            status: variant.status.or(self.status),
+            headers,
+            expose: variant.expose.or(self.expose),
+        }
+    }
+
+    /// Expression building the reply through the formatter `fmt`
+    fn reply(&self) -> TokenStream {
+        let private = quote!(::routerman::__private);
+        let status = self.status.unwrap_or(500);
+        let names = self.headers.iter().map(|(name, _)| name);
+        let values = self.headers.iter().map(|(_, value)| value);
+
+        // The status code was validated when parsing the attributes
+        let status = quote! {
+            match #private::StatusCode::from_u16(#status) {
+                ::std::result::Result::Ok(status) => status,
+                ::std::result::Result::Err(_) => ::std::unreachable!(),
+            }
+        };
+        let error = match self.expose.unwrap_or(false) {
+            true => quote!(&self),
+            false => quote!(&#private::RedactedError(status)),
+        };
+
+        quote! {{
+            let status = #status;
+            let mut res = ::routerman::response::Formatter::format_error(&fmt, #error, status);
+            #(res.headers_mut().insert(
+                #private::HeaderName::from_static(#names),
+                #private::HeaderValue::from_static(#values),
+            );)*
+            res
+        }}
+    }
+}
+
+/// Expand `#[derive(Reply)]`
+pub fn expand(input: DeriveInput) -> Result<TokenStream> {
+    let type_attrs = ReplyAttrs::parse(&input.attrs)?;
+
+    let mut arms = Vec::new();
+    match &input.data {
+        Data::Struct(_) => {
+            let expr = type_attrs.reply();
+            arms.push(quote!(_ => #expr));
+        }
+        Data::Enum(data) => {
+            for variant in &data.variants {
+                let attrs = type_attrs.extend(ReplyAttrs::parse(&variant.attrs)?);
+                let expr = attrs.reply();
+
+                let ident = &variant.ident;
+                let pattern = match variant.fields {
+                    Fields::Named(_) => quote!(Self::#ident { .. }),
+                    Fields::Unnamed(_) => quote!(Self::#ident(..)),
+                    Fields::Unit => quote!(Self::#ident),
+                };
+                arms.push(quote!(#pattern => #expr));
+            }
+        }
+        Data::Union(data) => {
+            return Err(Error::new(
+                data.union_token.span(),
+                "`Reply` cannot be derived for unions",
+            ))
+        }
+    }
+
+    // Replies are formatted as errors, so they are supported by any formatter
+    let fmt = format_ident!("__Fmt");
+    let mut generics = input.generics.clone();
+    generics
+        .params
+        .push(parse_quote!(#fmt: ::routerman::response::Formatter));
+    generics
+        .make_where_clause()
+        .predicates
+        .push(parse_quote!(Self: ::std::error::Error));
+
+    // Enums without variants can't be matched by reference
+    let body = match arms.is_empty() {
+        true => quote!(match self {}),
+        false => quote! {
+            match &self {
+                #(#arms,)*
+            }
+        },
+    };
+
+    let ident = &input.ident;
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
+    let (_, ty_generics, _) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::routerman::response::Reply<#fmt> for #ident #ty_generics
+        #where_clause
+        {
+            fn reply(self, fmt: #fmt) -> ::routerman::response::Response {
+                #body
+            }
+        }
+    })
+}
+
+/// Whether a string is a valid lowercase header name
+fn is_header_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.bytes().all(|b| {
+            b.is_ascii_lowercase() || b.is_ascii_digit() || b"!#$%&'*+-.^_`|~".contains(&b)
+        })
+}
+
+/// Whether a string is a valid header value
+fn is_header_value(value: &str) -> bool {
+    value
+        .bytes()
+        .all(|b| b == b'\t' || (0x20..0x7f).contains(&b))
+}
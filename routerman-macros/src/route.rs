@@ -0,0 +1,188 @@
+use crate::pattern::{self, Token};
+use proc_macro2::{Span, TokenStream};
+use quote::{format_ident, quote};
+use syn::{spanned::Spanned, Error, FnArg, ItemFn, LitStr, Pat, PatIdent, Result};
+
+/// Expand a route attribute (eg. `#[get("/path")]`) applied to a handler function
+pub fn expand(method: &str, attr: TokenStream, item: TokenStream) -> Result<TokenStream> {
+    let path = syn::parse2::<LitStr>(attr)?;
+    let path_value = path.value();
+    let params = parse_path(&path_value).map_err(|msg| Error::new(path.span(), msg))?;
+
+    let ItemFn {
+        attrs,
+        vis,
+        sig,
+        block,
+    } = syn::parse2::<ItemFn>(item)?;
+
+    if sig.asyncness.is_none() {
+        return Err(Error::new(
+            sig.fn_token.span,
+            "route handlers must be async",
+        ));
+    }
+    if !sig.generics.params.is_empty() || sig.generics.where_clause.is_some() {
+        return Err(Error::new(
+            sig.generics.span(),
+            "route handlers cannot be generic",
+        ));
+    }
+
+    // Split the arguments into path parameters (bound by name) and extractors
+    let mut param_args = Vec::new();
+    let mut extractor_args = Vec::new();
+    let mut call_args = Vec::new();
+    for (i, arg) in sig.inputs.iter().enumerate() {
+        let arg = match arg {
+            FnArg::Typed(arg) => arg,
+            FnArg::Receiver(arg) => {
+                return Err(Error::new(arg.span(), "route handlers cannot take `self`"))
+            }
+        };
+
+        match &*arg.pat {
+            Pat::Ident(PatIdent {
+                ident,
+                by_ref: None,
+                subpat: None,
+                ..
+            }) if params.iter().any(|param| ident == param) => {
+                param_args.push((ident.clone(), arg.ty.clone()));
+                call_args.push(quote!(#ident));
+            }
+            _ => {
+                let ident = format_ident!("__arg{}", i, span = Span::mixed_site());
+                let ty = &arg.ty;
+                extractor_args.push(quote!(#ident: #ty));
+                call_args.push(quote!(#ident));
+            }
+        }
+    }
+
+    // Every parameter must be bound, in the order they appear in the path
+    let mut param_idents = Vec::new();
+    let mut param_tys = Vec::new();
+    for param in &params {
+        match param_args.iter().find(|(ident, _)| ident == param) {
+            Some((ident, ty)) => {
+                param_idents.push(ident);
+                param_tys.push(ty);
+            }
+            None => {
+                return Err(Error::new(
+                    path.span(),
+                    format!("path parameter `{}` is not bound by any argument", param),
+                ))
+            }
+        }
+    }
+
+    let path_arg = match params.is_empty() {
+        true => quote!(),
+        false => quote! {
+            ::routerman::request::path::Path((#(#param_idents,)*)):
+                ::routerman::request::path::Path<(#(#param_tys,)*)>,
+        },
+    };
+
+    // Documentation is kept on the generated function, while other attributes apply to the handler
+    let (doc_attrs, attrs): (Vec<_>, Vec<_>) = attrs
+        .into_iter()
+        .partition(|attr| attr.path().is_ident("doc"));
+
+    let name = &sig.ident;
+    let method = format_ident!("{}", method);
+    Ok(quote! {
+        #(#doc_attrs)*
+        #vis fn #name<__Fmt>() -> ::routerman::router::RouterBuilder<__Fmt>
+        where
+            __Fmt: ::routerman::response::Formatter,
+        {
+            #(#attrs)*
+            #sig #block
+
+            ::routerman::router::Router::builder().route(
+                #path,
+                ::routerman::method::#method(
+                    |#path_arg #(#extractor_args),*| #name(#(#call_args),*)
+                ),
+            )
+        }
+    })
+}
+
+/// Validate a route's path, returning the names of its parameters. Mirrors the rules enforced when
+/// routes are inserted into the router.
+fn parse_path(path: &str) -> std::result::Result<Vec<&str>, String> {
+    if !path.starts_with('/') {
+        return Err("route paths must start with `/`".into());
+    }
+
+    let mut params = Vec::new();
+    let mut after_slash = false;
+    for token in pattern::tokenize(path) {
+        let name = match token {
+            Token::Static(text) => {
+                after_slash = text.ends_with('/');
+                continue;
+            }
+            Token::CatchAll(name) if name.contains('/') || !after_slash => {
+                return Err(
+                    "catch-all parameters are only allowed at the end of a path, after a `/`"
+                        .into(),
+                )
+            }
+            Token::Param(name) | Token::CatchAll(name) => name,
+        };
+        after_slash = false;
+
+        if name.is_empty() {
+            return Err("path parameters must be named".into());
+        }
+        if name.contains([':', '*']) {
+            return Err("only one parameter is allowed per path segment".into());
+        }
+        if params.contains(&name) {
+            return Err(format!("duplicate path parameter `{}`", name));
+        }
+        params.push(name);
+    }
+
+    Ok(params)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_path;
+
+    #[test]
+    fn valid_paths() {
+        assert_eq!(parse_path("/"), Ok(vec![]));
+        assert_eq!(parse_path("/users/:id/posts/:post"), Ok(vec!["id", "post"]));
+        assert_eq!(parse_path("/files/:user/*path"), Ok(vec!["user", "path"]));
+    }
+
+    #[test]
+    fn invalid_paths() {
+        for (path, err) in [
+            ("users", "route paths must start with `/`"),
+            ("/users/:", "path parameters must be named"),
+            (
+                "/users/:a:b",
+                "only one parameter is allowed per path segment",
+            ),
+            (
+                "/files/*path/x",
+                "catch-all parameters are only allowed at the end of a path, after a `/`",
+            ),
+            (
+                "/files*path",
+                "catch-all parameters are only allowed at the end of a path, after a `/`",
+            ),
+            ("/:id/*id", "duplicate path parameter `id`"),
+        ] {
+            assert_eq!(parse_path(path), Err(err.to_owned()), "{path}");
+        }
+    }
+}
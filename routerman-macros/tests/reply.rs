@@ -0,0 +1,84 @@
+use routerman::{
+    method::get, problem::ProblemJsonFormatter, response::Reply, router::RouterBuilder,
+    test::TestClient,
+};
+use serde_json::{json, Value};
+
+#[derive(Debug, thiserror::Error, Reply)]
+#[reply(status = 400, expose)]
+enum UploadError {
+    #[error("file too large")]
+    #[reply(status = 413, header("x-max-size", "1024"))]
+    TooLarge,
+
+    #[error("failed to store `{0}`")]
+    #[reply(status = 503, header("retry-after", "120"), expose = false)]
+    Storage(String),
+
+    #[error("missing file name")]
+    MissingName,
+}
+
+#[derive(Debug, thiserror::Error, Reply)]
+#[error("secret database error")]
+struct DatabaseError;
+
+fn client() -> TestClient<ProblemJsonFormatter> {
+    TestClient::new(
+        RouterBuilder::<ProblemJsonFormatter>::new()
+            .route(
+                "/large",
+                get(|| async { Err::<(), _>(UploadError::TooLarge) }),
+            )
+            .route(
+                "/storage",
+                get(|| async { Err::<(), _>(UploadError::Storage("/secret".into())) }),
+            )
+            .route(
+                "/unnamed",
+                get(|| async { Err::<(), _>(UploadError::MissingName) }),
+            )
+            .route("/database", get(|| async { Err::<(), _>(DatabaseError) }))
+            .build(),
+    )
+}
+
+#[tokio::test]
+async fn replies_through_formatter() {
+    let client = client();
+
+    let res = client.get("/large").send().await;
+    assert_eq!(res.status().as_u16(), 413);
+    assert_eq!(res.header("content-type"), Some("application/problem+json"));
+    assert_eq!(res.header("x-max-size"), Some("1024"));
+    let problem = res.json::<Value>().await;
+    assert_eq!(problem["status"], json!(413));
+    assert_eq!(problem["detail"], json!("file too large"));
+    assert_eq!(problem["instance"], json!("/large"));
+
+    let res = client.get("/unnamed").send().await;
+    assert_eq!(res.status().as_u16(), 400);
+    assert_eq!(
+        res.json::<Value>().await["detail"],
+        json!("missing file name")
+    );
+}
+
+#[tokio::test]
+async fn variants_can_opt_out_of_expose() {
+    let res = client().get("/storage").send().await;
+    assert_eq!(res.status().as_u16(), 503);
+    assert_eq!(res.header("content-type"), Some("application/problem+json"));
+    assert_eq!(res.header("retry-after"), Some("120"));
+    let problem = res.json::<Value>().await;
+    assert_eq!(problem["status"], json!(503));
+    assert!(!problem.to_string().contains("secret"));
+}
+
+#[tokio::test]
+async fn messages_are_not_exposed_by_default() {
+    let res = client().get("/database").send().await;
+    assert_eq!(res.status().as_u16(), 500);
+    assert_eq!(res.header("content-type"), Some("application/problem+json"));
+    assert!(!res.text().await.contains("secret"));
+}
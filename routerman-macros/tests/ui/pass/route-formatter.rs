@@ -0,0 +1,27 @@
+use routerman::{
+    get,
+    json::Json,
+    post,
+    problem::ProblemJsonFormatter,
+    response::DefaultFormatter,
+    router::{Router, RouterBuilder},
+};
+
+#[get("/users/:id")]
+async fn show_user(id: u32) -> String {
+    format!("User {}", id)
+}
+
+#[post("/users")]
+async fn create_user(Json(name): Json<String>) -> String {
+    name
+}
+
+fn main() {
+    let _: Router<ProblemJsonFormatter> = RouterBuilder::<ProblemJsonFormatter>::new()
+        .merge(show_user())
+        .merge(create_user())
+        .build();
+
+    let _: Router<DefaultFormatter> = Router::builder().merge(show_user()).build();
+}
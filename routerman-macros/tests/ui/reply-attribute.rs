@@ -0,0 +1,15 @@
+use routerman::response::Reply;
+
+#[derive(Debug, thiserror::Error, Reply)]
+#[error("unknown attribute")]
+#[reply(body = "hello")]
+struct UnknownAttribute;
+
+#[derive(Debug, thiserror::Error, Reply)]
+enum InvalidExpose {
+    #[error("invalid expose")]
+    #[reply(expose = "yes")]
+    Variant,
+}
+
+fn main() {}
@@ -0,0 +1,13 @@
+use routerman::response::Reply;
+
+#[derive(Debug, thiserror::Error, Reply)]
+#[error("uppercase name")]
+#[reply(header("Retry-After", "120"))]
+struct UppercaseName;
+
+#[derive(Debug, thiserror::Error, Reply)]
+#[error("invalid value")]
+#[reply(header("x-value", "a\nb"))]
+struct InvalidValue;
+
+fn main() {}
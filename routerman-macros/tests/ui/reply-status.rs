@@ -0,0 +1,13 @@
+use routerman::response::Reply;
+
+#[derive(Debug, thiserror::Error, Reply)]
+#[error("too large")]
+#[reply(status = 1000)]
+struct TooLarge;
+
+#[derive(Debug, thiserror::Error, Reply)]
+#[error("too small")]
+#[reply(status = 99)]
+struct TooSmall;
+
+fn main() {}
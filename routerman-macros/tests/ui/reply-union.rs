@@ -0,0 +1,9 @@
+use routerman::response::Reply;
+
+#[derive(Reply)]
+union Value {
+    int: u32,
+    float: f32,
+}
+
+fn main() {}
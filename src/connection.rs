@@ -0,0 +1,124 @@
+//! Information about the connections requests arrive through
+//!
+//! A [`Router`](crate::router::Router) can serve any connection type implementing [`Connected`],
+//! such as hyper's `AddrStream` or, with the `tokio` feature, tokio's `TcpStream` and
+//! `UnixStream`.
+
+#[cfg(feature = "tls")]
+use crate::tls::TlsInfo;
+use hyper::server::conn::AddrStream;
+use std::net::SocketAddr;
+#[cfg(unix)]
+use std::path::PathBuf;
+
+/// Connection a request arrived through. Available to handlers through
+/// [`RequestExt::connection_info`], or directly from the request's extensions.
+///
+/// [`RequestExt::connection_info`]: crate::request::RequestExt::connection_info
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ConnectionInfo {
+    /// TCP connection, along with the peer's address
+    Tcp(SocketAddr),
+
+    /// Unix domain socket connection
+    #[cfg(unix)]
+    Unix(UnixPeer),
+
+    /// Connection of a type that provides no information
+    Unknown,
+}
+
+impl ConnectionInfo {
+    /// Address of the peer, if connected through TCP
+    pub fn remote_addr(&self) -> Option<&SocketAddr> {
+        match self {
+            ConnectionInfo::Tcp(addr) => Some(addr),
+            _ => None,
+        }
+    }
+}
+
+/// Peer of a Unix domain socket connection
+#[cfg(unix)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnixPeer {
+    /// Path the peer's socket is bound to. Clients usually connect through unnamed sockets.
+    pub path: Option<PathBuf>,
+
+    /// Credentials of the peer's process, if supported by the platform
+    pub credentials: Option<UnixCredentials>,
+}
+
+/// Credentials of the process on the other end of a Unix domain socket
+#[cfg(unix)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UnixCredentials {
+    pub uid: u32,
+    pub gid: u32,
+    /// Process id, if supported by the platform
+    pub pid: Option<i32>,
+}
+
+/// Connection type that a router can serve requests over
+pub trait Connected {
+    fn connection_info(&self) -> ConnectionInfo;
+
+    /// Details of the TLS session, if the connection is encrypted
+    #[cfg(feature = "tls")]
+    fn tls_info(&self) -> Option<TlsInfo> {
+        None
+    }
+}
+
+impl Connected for AddrStream {
+    fn connection_info(&self) -> ConnectionInfo {
+        ConnectionInfo::Tcp(self.remote_addr())
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl Connected for tokio::net::TcpStream {
+    fn connection_info(&self) -> ConnectionInfo {
+        match self.peer_addr() {
+            Ok(addr) => ConnectionInfo::Tcp(addr),
+            Err(_) => ConnectionInfo::Unknown,
+        }
+    }
+}
+
+/// Serving a router over a Unix domain socket:
+/// ```no_run
+/// # use routerman::{method::get, request::{Request, RequestExt}, router::Router};
+/// # use hyper::{server::accept, Server};
+/// # use tokio::net::UnixListener;
+/// # #[tokio::main]
+/// # async fn main() {
+/// let router: Router = Router::builder()
+///     .route("/", get(|req: Request| async move { format!("{:?}", req.connection_info()) }))
+///     .build();
+///
+/// let listener = UnixListener::bind("/tmp/routerman.sock").unwrap();
+/// let incoming = accept::poll_fn(move |cx| match listener.poll_accept(cx) {
+///     std::task::Poll::Ready(res) => std::task::Poll::Ready(Some(res.map(|(stream, _)| stream))),
+///     std::task::Poll::Pending => std::task::Poll::Pending,
+/// });
+/// Server::builder(incoming).serve(router).await.unwrap();
+/// # }
+/// ```
+#[cfg(all(unix, feature = "tokio"))]
+impl Connected for tokio::net::UnixStream {
+    fn connection_info(&self) -> ConnectionInfo {
+        ConnectionInfo::Unix(UnixPeer {
+            path: self
+                .peer_addr()
+                .ok()
+                .and_then(|addr| addr.as_pathname().map(PathBuf::from)),
+            credentials: self.peer_cred().ok().map(|cred| UnixCredentials {
+                uid: cred.uid(),
+                gid: cred.gid(),
+                pid: cred.pid(),
+            }),
+        })
+    }
+}
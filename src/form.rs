@@ -0,0 +1,152 @@
+//! Utilities for url-encoded form serialization and deserialization
+
+use crate::mime;
+use crate::request::extract::ExtractFrom;
+use crate::response::{Formatter, Reply};
+use futures_util::Future;
+use hyper::body::HttpBody;
+use hyper::{body::Bytes, header, Body, Response};
+use hyper::{Request, StatusCode};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::error::Error as StdError;
+use std::fmt::{Debug, Display};
+use std::future::{ready, Ready};
+use std::pin::Pin;
+
+/// Helper for managing `application/x-www-form-urlencoded` responses and requests
+///
+/// Wrapper type for serializable and/or deserializable objects that can be extracted from a request
+/// body or converted into a response. Repeated keys can be deserialized into a `Vec`.
+///
+/// Works the same way as [`Json`](crate::json::Json):
+/// ```
+/// # use routerman::{form::Form, method::post, router::Router};
+/// # use serde::Deserialize;
+/// #[derive(Deserialize)]
+/// struct Login {
+///     username: String,
+///     password: String,
+/// }
+///
+/// let router: Router = Router::builder()
+///     .route(
+///         "/login",
+///         post(|Form(login): Form<Login>| async move { format!("Hello, {}", login.username) }),
+///     )
+///     .build();
+/// ```
+#[derive(Debug, Clone)]
+pub struct Form<T>(
+    /// The stored form object
+    pub T,
+);
+
+pub use serde_html_form::de::Error as FormError;
+pub use serde_html_form::ser::Error as FormSerializeError;
+
+/// Form processing error
+pub enum Error<B: HttpBody> {
+    Body(B::Error),
+    Form(FormError),
+}
+
+impl<B: HttpBody> Display for Error<B>
+where
+    B::Error: Display,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Body(err) => write!(f, "body error: {}", err),
+            Error::Form(err) => write!(f, "form error: {}", err),
+        }
+    }
+}
+
+impl<B: HttpBody> Debug for Error<B>
+where
+    B::Error: Debug,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Body(arg0) => f.debug_tuple("Body").field(arg0).finish(),
+            Self::Form(arg0) => f.debug_tuple("Form").field(arg0).finish(),
+        }
+    }
+}
+
+impl<B: HttpBody> StdError for Error<B>
+where
+    B::Error: StdError + 'static,
+{
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        match self {
+            Error::Body(err) => Some(err),
+            Error::Form(err) => Some(err),
+        }
+    }
+}
+
+impl<F: Formatter> Reply<F> for FormSerializeError {
+    fn reply(self, fmt: F) -> Response<Body> {
+        fmt.internal_error(&self)
+    }
+}
+
+impl<F: Formatter> Reply<F> for Error<Body> {
+    fn reply(self, fmt: F) -> Response<Body> {
+        fmt.format_error(&self, StatusCode::BAD_REQUEST)
+    }
+}
+
+impl<T, Fmt> Reply<Fmt> for Form<T>
+where
+    T: Serialize,
+    FormSerializeError: Reply<Fmt>,
+    hyper::http::Error: Reply<Fmt>,
+{
+    fn reply(self, fmt: Fmt) -> Response<Body> {
+        match serde_html_form::to_string(&self.0) {
+            Ok(content) => (
+                [(
+                    header::CONTENT_TYPE,
+                    mime::APPLICATION_WWW_FORM_URLENCODED.header(),
+                )],
+                content.into_bytes(),
+            )
+                .reply(fmt),
+            Err(err) => err.reply(fmt),
+        }
+    }
+}
+
+impl<'a, T> ExtractFrom<&'a Bytes> for Form<T>
+where
+    T: Deserialize<'a>,
+{
+    type Error = FormError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn extract_from(bytes: &'a Bytes) -> Self::Future {
+        ready(serde_html_form::from_bytes(bytes.as_ref()).map(Form))
+    }
+}
+
+impl<T, B> ExtractFrom<Request<B>> for Form<T>
+where
+    T: DeserializeOwned,
+    B: HttpBody + Send + 'static,
+    B::Data: Send,
+{
+    type Error = Error<B>;
+    type Future = Pin<Box<dyn Future<Output = Result<Self, Self::Error>> + Send + 'static>>;
+
+    fn extract_from(req: Request<B>) -> Self::Future {
+        Box::pin(async move {
+            let bytes = Bytes::extract_from(req).await.map_err(Error::Body)?;
+            serde_html_form::from_bytes(bytes.as_ref())
+                .map_err(Error::Form)
+                .map(Form)
+        })
+    }
+}
@@ -2,7 +2,7 @@
 
 use crate::mime;
 use crate::request::extract::ExtractFrom;
-use crate::response::{DefaultFormatter, Reply};
+use crate::response::{Formatter, Reply};
 use futures_util::Future;
 use hyper::body::HttpBody;
 use hyper::{body::Bytes, header, Body, Response};
@@ -25,13 +25,12 @@ use std::pin::Pin;
 /// Returning a json object is also possible, in which case it will automatically be converted into
 /// a response.
 /// ```
-/// # use hyper::{Request, Body, body::Bytes};
 /// # use routerman::{
-/// #   route::Route, json::Json,
-/// #   request::extract::ExtractFrom, response::{IntoResponse, DefaultFormatter},
+/// #   route::Route, json::Json, request::{Request, extract::ExtractFrom},
+/// #   response::DefaultFormatter,
 /// # };
 /// # use std::collections::HashMap;
-/// Route::<_, _, DefaultFormatter>::new(|req: Request<Body>| async move {
+/// Route::<DefaultFormatter>::new(|req: Request| async move {
 ///     // Parse json from user input
 ///     let data: HashMap<String, String> = Json::extract_from(req).await.unwrap().0;
 ///
@@ -44,13 +43,13 @@ use std::pin::Pin;
 /// Bytes object the deserialized json will reference and maintain it for the duration of the
 /// request.
 /// ```
-/// # use hyper::{Request, Body, body::Bytes};
+/// # use hyper::body::Bytes;
 /// # use routerman::{
-/// #   route::Route, json::Json,
-/// #   request::extract::ExtractFrom, response::{IntoResponse, DefaultFormatter},
+/// #   route::Route, json::Json, request::{Request, extract::ExtractFrom},
+/// #   response::DefaultFormatter,
 /// # };
 /// # use std::collections::HashMap;
-/// Route::<_, _, DefaultFormatter>::new(|req: Request<Body>| async move {
+/// Route::<DefaultFormatter>::new(|req: Request| async move {
 ///     let bytes = Bytes::extract_from(req).await.unwrap();
 ///     let data: HashMap<&str, &str> = Json::extract_from(&bytes).await.unwrap().0;
 ///
@@ -59,20 +58,20 @@ use std::pin::Pin;
 /// ```
 ///
 /// However, because the json object is now referencing a local variable it cannot be returned to
-/// the user. This can be circumvented by manually calling [`IntoResponse::into_response`] and
-/// then returning the response object:
+/// the user. This can be circumvented by manually calling [`Reply::reply`] and then returning the
+/// response object:
 /// ```
-/// # use hyper::{Request, Body, body::Bytes};
+/// # use hyper::body::Bytes;
 /// # use routerman::{
-/// #   route::Route, json::Json,
-/// #   request::extract::ExtractFrom, response::{IntoResponse, DefaultFormatter},
+/// #   route::Route, json::Json, request::{Request, extract::ExtractFrom},
+/// #   response::{Reply, DefaultFormatter},
 /// # };
 /// # use std::collections::HashMap;
-/// Route::<_, _, DefaultFormatter>::with_fmt(|req: Request<Body>, fmt| async move {
+/// Route::<DefaultFormatter>::new(|req: Request, fmt| async move {
 ///     let bytes = Bytes::extract_from(req).await.unwrap();
 ///     let data: HashMap<&str, &str> = Json::extract_from(&bytes).await.unwrap().0;
 ///
-///     Json(data).into_response(fmt).0
+///     Json(data).reply(fmt)
 /// });
 /// ```
 #[derive(Debug, Clone)]
@@ -125,9 +124,15 @@ where
     }
 }
 
-impl Reply<serde_json::Error> for DefaultFormatter {
-    fn reply(self, err: serde_json::Error) -> Response<Body> {
-        (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()).reply(self)
+impl<F: Formatter> Reply<F> for serde_json::Error {
+    fn reply(self, fmt: F) -> Response<Body> {
+        fmt.internal_error(&self)
+    }
+}
+
+impl<F: Formatter> Reply<F> for Error<Body> {
+    fn reply(self, fmt: F) -> Response<Body> {
+        fmt.format_error(&self, StatusCode::BAD_REQUEST)
     }
 }
 
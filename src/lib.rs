@@ -3,13 +3,61 @@
 //! Just imagine all the documentation. Cause that's all you can do for now since I haven't written
 //! it.
 
+#[macro_use]
+mod macros;
+
+pub mod connection;
 pub mod method;
 pub mod request;
 pub mod response;
 pub mod route;
 pub mod router;
+pub mod test;
 
 #[cfg(feature = "json")]
 pub mod json;
 
+#[cfg(feature = "json")]
+pub mod problem;
+
+#[cfg(feature = "form")]
+pub mod form;
+
+#[cfg(feature = "openapi")]
+pub mod openapi;
+
+#[cfg(feature = "server")]
+pub mod server;
+
+#[cfg(feature = "tls")]
+pub mod tls;
+
 mod mime;
+mod pattern;
+
+#[cfg(feature = "server")]
+pub use server::serve;
+
+#[cfg(feature = "macros")]
+pub use routerman_macros::{connect, delete, get, head, options, patch, post, put, trace};
+
+/// Items used by the code generated by the macros
+#[cfg(feature = "macros")]
+#[doc(hidden)]
+pub mod __private {
+    pub use hyper::header::{HeaderName, HeaderValue};
+    pub use hyper::StatusCode;
+
+    /// Error replied in place of an error whose message must not be exposed, described by its
+    /// status code's canonical reason
+    #[derive(Debug)]
+    pub struct RedactedError(pub StatusCode);
+
+    impl std::fmt::Display for RedactedError {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.write_str(self.0.canonical_reason().unwrap_or("Unknown error"))
+        }
+    }
+
+    impl std::error::Error for RedactedError {}
+}
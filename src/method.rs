@@ -1,14 +1,26 @@
+#[cfg(feature = "openapi")]
+use crate::openapi::Operation;
 use crate::{
     request::Request,
-    response::Reply,
-    route::{Route, RouteHandler},
+    response::{Formatter, Reply, Response},
+    route::{Route, RouteHandler, RouteService},
+};
+use futures_util::FutureExt;
+use hyper::{
+    body::HttpBody,
+    header::{self, HeaderValue},
+    Body, Method, StatusCode,
 };
-use hyper::{header::HeaderValue, Method};
 use std::{collections::HashMap, future::ready};
+use tower_layer::Layer;
+use tower_service::Service;
 
 pub struct MethodRouter<Fmt> {
     handlers: HashMap<Method, Route<Fmt>>,
     fallback: MethodFallback<Fmt>,
+
+    #[cfg(feature = "openapi")]
+    operations: HashMap<Method, Operation>,
 }
 
 enum MethodFallback<Fmt> {
@@ -23,6 +35,8 @@ impl<Fmt> MethodRouter<Fmt> {
             fallback: MethodFallback::None {
                 allow_header: HeaderValue::from_static(""),
             },
+            #[cfg(feature = "openapi")]
+            operations: HashMap::new(),
         }
     }
 
@@ -56,8 +70,28 @@ impl<Fmt> MethodRouter<Fmt> {
         self
     }
 
+    /// Describe every method handled so far in the generated OpenAPI document. Combine method
+    /// routers to describe each method separately:
+    /// ```
+    /// # use routerman::{method::{get, post}, openapi::Operation, request::Request};
+    /// # use routerman::response::DefaultFormatter;
+    /// let _: routerman::method::MethodRouter<DefaultFormatter> =
+    ///     get(|_req: Request| async { "List" }).operation(Operation::new().summary("List items"))
+    ///         | post(|_req: Request| async { "Create" })
+    ///             .operation(Operation::new().summary("Create an item"));
+    /// ```
+    #[cfg(feature = "openapi")]
+    pub fn operation(mut self, operation: Operation) -> Self {
+        for method in self.handlers.keys() {
+            self.operations.insert(method.clone(), operation.clone());
+        }
+        self
+    }
+
     pub fn merge(&mut self, other: Self) {
-        self.handlers.extend(other.handlers.into_iter());
+        self.handlers.extend(other.handlers);
+        #[cfg(feature = "openapi")]
+        self.operations.extend(other.operations);
         match (&self.fallback, other.fallback) {
             (MethodFallback::Route(_), MethodFallback::Route(_)) => {
                 panic!("Cannot merge two method routers with fallback routes")
@@ -75,6 +109,35 @@ impl<Fmt> MethodRouter<Fmt> {
         self
     }
 
+    /// Wrap every route of the method router, including the fallback route, with a tower [`Layer`].
+    /// See [`Route::layer`].
+    ///
+    /// Only the methods handled so far are wrapped: Method routers merged afterwards (eg. with `|`)
+    /// are not affected. Neither are the replies generated by the method router itself (eg.
+    /// `405 Method Not Allowed`).
+    pub fn layer<L>(self, layer: L) -> Self
+    where
+        L: Layer<RouteService<Fmt>> + Clone + Send + Sync + 'static,
+        L::Service: Service<Request, Response = Response> + Clone + Send + 'static,
+        <L::Service as Service<Request>>::Future: Send + 'static,
+        <L::Service as Service<Request>>::Error: Reply<Fmt>,
+        Fmt: Formatter,
+    {
+        Self {
+            handlers: self
+                .handlers
+                .into_iter()
+                .map(|(method, route)| (method, route.layer(layer.clone())))
+                .collect(),
+            fallback: match self.fallback {
+                MethodFallback::Route(route) => MethodFallback::Route(route.layer(layer)),
+                fallback => fallback,
+            },
+            #[cfg(feature = "openapi")]
+            operations: self.operations,
+        }
+    }
+
     fn update_allow_header(&mut self) {
         if let MethodFallback::None { allow_header } = &mut self.fallback {
             let mut methods = self
@@ -82,10 +145,22 @@ impl<Fmt> MethodRouter<Fmt> {
                 .keys()
                 .map(|method| format!("{}", method))
                 .collect::<Vec<_>>();
+
+            // HEAD is implicitly handled by the GET route, unless explicitly overriden
+            if self.handlers.contains_key(&Method::GET)
+                && !self.handlers.contains_key(&Method::HEAD)
+            {
+                methods.push(format!("{}", Method::HEAD));
+            }
+
+            // OPTIONS is always handled, either explicitly or implicitly
+            if !self.handlers.contains_key(&Method::OPTIONS) {
+                methods.push(format!("{}", Method::OPTIONS));
+            }
             methods.sort();
 
             // Header names are possible to encode in http, so this should never panic
-            *allow_header = HeaderValue::from_str(&*methods.join(", ")).unwrap();
+            *allow_header = HeaderValue::from_str(&methods.join(", ")).unwrap();
         }
     }
 }
@@ -132,30 +207,123 @@ impl<Fmt> core::ops::BitOrAssign<Self> for MethodRouter<Fmt> {
     }
 }
 
+/// Reply sent when a method router has no route for the request's method and no fallback route.
 pub struct MethodNotAllowed<'a> {
     pub allow_header: &'a HeaderValue,
 }
 
+/// Reply sent to an OPTIONS request when a method router has no explicit OPTIONS route and no
+/// fallback route.
+pub struct MethodOptions<'a> {
+    pub allow_header: &'a HeaderValue,
+}
+
 impl<Fmt> RouteHandler<Fmt, ()> for MethodRouter<Fmt>
 where
     Fmt: Send + Sync + 'static,
     for<'a> MethodNotAllowed<'a>: Reply<Fmt>,
+    for<'a> MethodOptions<'a>: Reply<Fmt>,
 {
     fn into_route(self) -> Route<Fmt> {
-        Route::new(
-            move |req: Request, fmt: Fmt| match self.handlers.get(req.method()) {
-                Some(route) => (route.handler_fn())(req, fmt),
-                None => match &self.fallback {
+        // A fallback route accepts any method
+        let methods = match self.fallback {
+            MethodFallback::Route(_) => None,
+            MethodFallback::None { .. } => {
+                let mut methods = self.handlers.keys().cloned().collect::<Vec<_>>();
+                methods.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+                Some(methods.into())
+            }
+        };
+
+        #[cfg(feature = "openapi")]
+        let operations = {
+            let mut operations = self.operations.into_iter().collect::<Vec<_>>();
+            operations.sort_by(|(a, _), (b, _)| a.as_str().cmp(b.as_str()));
+            operations
+        };
+
+        let required_state = self
+            .handlers
+            .values()
+            .chain(match &self.fallback {
+                MethodFallback::Route(route) => Some(route),
+                MethodFallback::None { .. } => None,
+            })
+            .flat_map(|route| route.required_state().iter().copied())
+            .collect::<Vec<_>>();
+
+        // The routes are bound along with the method router, since some of them may need the
+        // router's formatter (eg. layered routes)
+        let route = Route::from_make_fn(move |fmt: &Fmt| {
+            let handlers = self
+                .handlers
+                .iter()
+                .map(|(method, route)| (method.clone(), route.clone().bind(fmt)))
+                .collect::<HashMap<_, _>>();
+            let fallback = match &self.fallback {
+                MethodFallback::Route(route) => MethodFallback::Route(route.clone().bind(fmt)),
+                MethodFallback::None { allow_header } => MethodFallback::None {
+                    allow_header: allow_header.clone(),
+                },
+            };
+
+            Route::new(move |req: Request, fmt: Fmt| {
+                if let Some(route) = handlers.get(req.method()) {
+                    return (route.handler_fn())(req, fmt);
+                }
+
+                // Without an explicit HEAD route, reply to HEAD requests by running the GET route
+                // and discarding the body
+                if req.method() == Method::HEAD {
+                    if let Some(route) = handlers.get(&Method::GET) {
+                        return Box::pin((route.handler_fn())(req, fmt).map(strip_body));
+                    }
+                }
+
+                match &fallback {
                     MethodFallback::Route(route) => (route.handler_fn())(req, fmt),
+                    MethodFallback::None { allow_header } if req.method() == Method::OPTIONS => {
+                        Box::pin(ready(MethodOptions { allow_header }.reply(fmt)))
+                    }
                     MethodFallback::None { allow_header } => {
                         Box::pin(ready(MethodNotAllowed { allow_header }.reply(fmt)))
                     }
-                },
-            },
-        )
+                }
+            })
+        })
+        .with_methods(methods)
+        .with_required_state(required_state);
+
+        #[cfg(feature = "openapi")]
+        let route = operations
+            .into_iter()
+            .fold(route, |route, (method, operation)| {
+                route.operation(method, operation)
+            });
+
+        route
     }
 }
 
+/// Remove the body of a response, while keeping its headers intact. If no `Content-Length` header
+/// was set, it is computed from the body (if the body's size is known).
+fn strip_body(mut res: Response) -> Response {
+    let status = res.status();
+    let can_have_length = !status.is_informational()
+        && status != StatusCode::NO_CONTENT
+        && status != StatusCode::NOT_MODIFIED;
+
+    if can_have_length && !res.headers().contains_key(header::CONTENT_LENGTH) {
+        if let Some(len) = res.body().size_hint().exact() {
+            res.headers_mut()
+                .insert(header::CONTENT_LENGTH, HeaderValue::from(len));
+        }
+    }
+
+    *res.body_mut() = Body::empty();
+    res
+}
+
 impl<Fmt> Default for MethodRouter<Fmt> {
     #[inline]
     fn default() -> Self {
@@ -20,3 +20,11 @@ pub const TEXT_PLAIN: Mime<'static> = Mime {
 pub const APPLICATION_JSON: Mime<'static> = Mime {
     source: "application/json",
 };
+#[cfg(feature = "json")]
+pub const APPLICATION_PROBLEM_JSON: Mime<'static> = Mime {
+    source: "application/problem+json",
+};
+#[cfg(feature = "form")]
+pub const APPLICATION_WWW_FORM_URLENCODED: Mime<'static> = Mime {
+    source: "application/x-www-form-urlencoded",
+};
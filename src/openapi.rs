@@ -0,0 +1,333 @@
+//! OpenAPI 3 document generation
+//!
+//! Operations are described by attaching [`Operation`] metadata to method routers (or routes), and
+//! the document is generated from the router's route table. Request and response schemas are
+//! generated from types implementing [`JsonSchema`].
+//! ```
+//! # use routerman::{
+//! #   json::Json, method::{get, post}, openapi::{JsonSchema, OpenApi, Operation},
+//! #   router::Router,
+//! # };
+//! # use hyper::StatusCode;
+//! # use serde::{Deserialize, Serialize};
+//! #[derive(Serialize, Deserialize, JsonSchema)]
+//! struct User {
+//!     name: String,
+//! }
+//!
+//! let router: Router = Router::builder()
+//!     .route(
+//!         "/users/:id",
+//!         get(|| async { Json(User { name: "Ferris".into() }) }).operation(
+//!             Operation::new()
+//!                 .summary("Get a user")
+//!                 .response_json::<User>(StatusCode::OK, "The user"),
+//!         ),
+//!     )
+//!     .route(
+//!         "/users",
+//!         post(|Json(user): Json<User>| async move { Json(user) }).operation(
+//!             Operation::new()
+//!                 .summary("Create a user")
+//!                 .request_json::<User>()
+//!                 .response_json::<User>(StatusCode::OK, "The created user"),
+//!         ),
+//!     )
+//!     .openapi_route("/openapi.json", OpenApi::new("Users", "1.0.0"))
+//!     .build();
+//!
+//! let document = router.openapi(&OpenApi::new("Users", "1.0.0"));
+//! assert_eq!(document["paths"]["/users/{id}"]["get"]["summary"], "Get a user");
+//! ```
+
+use crate::{
+    mime,
+    pattern::{self, Token},
+    router::RouteInfo,
+};
+use hyper::{Method, StatusCode};
+use schemars::{
+    gen::{SchemaGenerator, SchemaSettings},
+    schema::Schema,
+};
+use serde_json::{json, Map, Value};
+
+pub use schemars::JsonSchema;
+
+/// Version of the OpenAPI specification generated documents conform to
+const OPENAPI_VERSION: &str = "3.0.3";
+
+/// Generates the schema of a type, registering any definitions it depends on
+type SchemaFn = fn(&mut SchemaGenerator) -> Schema;
+
+fn subschema_for<T: JsonSchema>(gen: &mut SchemaGenerator) -> Schema {
+    gen.subschema_for::<T>()
+}
+
+/// Metadata describing an operation (a single method of a path) in an OpenAPI document
+#[derive(Debug, Clone, Default)]
+pub struct Operation {
+    summary: Option<String>,
+    description: Option<String>,
+    operation_id: Option<String>,
+    tags: Vec<String>,
+    deprecated: bool,
+    request_body: Option<SchemaFn>,
+    responses: Vec<OperationResponse>,
+}
+
+#[derive(Debug, Clone)]
+struct OperationResponse {
+    status: StatusCode,
+    description: String,
+    schema: Option<SchemaFn>,
+}
+
+impl Operation {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn summary(mut self, summary: impl Into<String>) -> Self {
+        self.summary = Some(summary.into());
+        self
+    }
+
+    pub fn description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    /// Unique identifier of the operation, used by code generators to name methods
+    pub fn operation_id(mut self, operation_id: impl Into<String>) -> Self {
+        self.operation_id = Some(operation_id.into());
+        self
+    }
+
+    pub fn tag(mut self, tag: impl Into<String>) -> Self {
+        self.tags.push(tag.into());
+        self
+    }
+
+    pub fn deprecated(mut self) -> Self {
+        self.deprecated = true;
+        self
+    }
+
+    /// Expect a json request body, as extracted by [`Json<T>`](crate::json::Json)
+    pub fn request_json<T: JsonSchema>(mut self) -> Self {
+        self.request_body = Some(subschema_for::<T>);
+        self
+    }
+
+    /// Document a response without a body
+    pub fn response(mut self, status: StatusCode, description: impl Into<String>) -> Self {
+        self.responses.push(OperationResponse {
+            status,
+            description: description.into(),
+            schema: None,
+        });
+        self
+    }
+
+    /// Document a json response, as replied by [`Json<T>`](crate::json::Json)
+    pub fn response_json<T: JsonSchema>(
+        mut self,
+        status: StatusCode,
+        description: impl Into<String>,
+    ) -> Self {
+        self.responses.push(OperationResponse {
+            status,
+            description: description.into(),
+            schema: Some(subschema_for::<T>),
+        });
+        self
+    }
+
+    fn to_json(&self, params: &[&str], gen: &mut SchemaGenerator) -> Value {
+        let mut operation = Map::new();
+        if let Some(summary) = &self.summary {
+            operation.insert("summary".into(), json!(summary));
+        }
+        if let Some(description) = &self.description {
+            operation.insert("description".into(), json!(description));
+        }
+        if let Some(operation_id) = &self.operation_id {
+            operation.insert("operationId".into(), json!(operation_id));
+        }
+        if !self.tags.is_empty() {
+            operation.insert("tags".into(), json!(self.tags));
+        }
+        if self.deprecated {
+            operation.insert("deprecated".into(), json!(true));
+        }
+
+        // Path parameters are always strings, since that's how they are matched
+        if !params.is_empty() {
+            let params = params
+                .iter()
+                .map(|name| {
+                    json!({
+                        "name": name,
+                        "in": "path",
+                        "required": true,
+                        "schema": { "type": "string" },
+                    })
+                })
+                .collect::<Vec<_>>();
+            operation.insert("parameters".into(), json!(params));
+        }
+
+        if let Some(schema) = self.request_body {
+            operation.insert(
+                "requestBody".into(),
+                json!({
+                    "required": true,
+                    "content": { mime::APPLICATION_JSON.as_str(): { "schema": schema(gen) } },
+                }),
+            );
+        }
+
+        // At least one response is required by the specification
+        let mut responses = Map::new();
+        for response in &self.responses {
+            let mut value = json!({ "description": response.description });
+            if let Some(schema) = response.schema {
+                value["content"] =
+                    json!({ mime::APPLICATION_JSON.as_str(): { "schema": schema(gen) } });
+            }
+            responses.insert(response.status.as_str().into(), value);
+        }
+        if responses.is_empty() {
+            responses.insert("default".into(), json!({ "description": "" }));
+        }
+        operation.insert("responses".into(), Value::Object(responses));
+
+        Value::Object(operation)
+    }
+}
+
+/// Top-level information of an OpenAPI document
+#[derive(Debug, Clone)]
+pub struct OpenApi {
+    title: String,
+    version: String,
+    description: Option<String>,
+}
+
+impl OpenApi {
+    /// Describe an api by its title and version (the version of the api, not of the specification)
+    pub fn new(title: impl Into<String>, version: impl Into<String>) -> Self {
+        Self {
+            title: title.into(),
+            version: version.into(),
+            description: None,
+        }
+    }
+
+    pub fn description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    /// Generate a document describing a set of routes. Every method of a route is described,
+    /// either by its [`Operation`] or by an empty one. Routes that accept any method are only
+    /// described by their explicit operations.
+    pub fn document<'a>(&self, routes: impl IntoIterator<Item = &'a RouteInfo>) -> Value {
+        let mut gen = SchemaSettings::openapi3().into_generator();
+
+        let mut paths = Map::new();
+        for route in routes {
+            let (path, params) = convert_path(route.path());
+
+            let mut methods = route.methods().unwrap_or_default().to_vec();
+            for (method, _) in route.operations() {
+                if !methods.contains(method) {
+                    methods.push(method.clone());
+                }
+            }
+
+            let mut item = Map::new();
+            for method in methods {
+                if !is_documented(&method) {
+                    continue;
+                }
+
+                let operation = route
+                    .operations()
+                    .iter()
+                    .find(|(m, _)| *m == method)
+                    .map(|(_, operation)| operation.to_json(&params, &mut gen))
+                    .unwrap_or_else(|| Operation::default().to_json(&params, &mut gen));
+                item.insert(method.as_str().to_ascii_lowercase(), operation);
+            }
+
+            if !item.is_empty() {
+                paths.insert(path, Value::Object(item));
+            }
+        }
+
+        let mut info = json!({ "title": self.title, "version": self.version });
+        if let Some(description) = &self.description {
+            info["description"] = json!(description);
+        }
+
+        json!({
+            "openapi": OPENAPI_VERSION,
+            "info": info,
+            "paths": paths,
+            "components": { "schemas": gen.take_definitions() },
+        })
+    }
+}
+
+/// Methods that can be described in an OpenAPI document
+fn is_documented(method: &Method) -> bool {
+    [
+        Method::GET,
+        Method::PUT,
+        Method::POST,
+        Method::DELETE,
+        Method::OPTIONS,
+        Method::HEAD,
+        Method::PATCH,
+        Method::TRACE,
+    ]
+    .contains(method)
+}
+
+/// Convert a route's path to OpenAPI's templating syntax (eg. `/users/:id` to `/users/{id}`),
+/// returning the names of its parameters
+fn convert_path(path: &str) -> (String, Vec<&str>) {
+    let mut converted = String::with_capacity(path.len());
+    let mut params = Vec::new();
+    for token in pattern::tokenize(path) {
+        match token {
+            Token::Static(text) => converted.push_str(text),
+            Token::Param(name) | Token::CatchAll(name) => {
+                converted.push_str(&format!("{{{}}}", name));
+                params.push(name);
+            }
+        }
+    }
+
+    (converted, params)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::convert_path;
+
+    #[test]
+    fn converts_params() {
+        assert_eq!(convert_path("/health"), ("/health".to_owned(), vec![]));
+        assert_eq!(
+            convert_path("/users/:id/posts/:post"),
+            ("/users/{id}/posts/{post}".to_owned(), vec!["id", "post"])
+        );
+        assert_eq!(
+            convert_path("/files/:user/*path"),
+            ("/files/{user}/{path}".to_owned(), vec!["user", "path"])
+        );
+    }
+}
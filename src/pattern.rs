@@ -0,0 +1,124 @@
+//! Tokenizer for route paths (eg. `/users/:id/files/*path`)
+//!
+//! This file is shared with routerman-macros, which includes it directly. It must not depend on
+//! anything else in the crate.
+
+/// Part of a route path
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Token<'a> {
+    /// Text matched as-is
+    Static(&'a str),
+
+    /// Parameter matching a single segment (`:name`), holding the parameter's name
+    Param(&'a str),
+
+    /// Parameter matching the rest of the path (`*name`), holding the parameter's name
+    CatchAll(&'a str),
+}
+
+/// Split a route path into tokens. Parameters end at the next segment, catch-alls at the end of
+/// the path.
+///
+/// The path is not validated: Parameter names may be empty or contain other parameters (eg.
+/// `/:a:b`), which the router rejects when the route is inserted.
+pub(crate) fn tokenize(path: &str) -> Tokens<'_> {
+    Tokens { rest: path }
+}
+
+/// Iterator over the tokens of a route path. See [`tokenize`].
+pub(crate) struct Tokens<'a> {
+    rest: &'a str,
+}
+
+impl<'a> Iterator for Tokens<'a> {
+    type Item = Token<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.rest.is_empty() {
+            return None;
+        }
+
+        let (token, rest) = match self.rest.find([':', '*']) {
+            Some(0) if self.rest.starts_with('*') => (Token::CatchAll(&self.rest[1..]), ""),
+            Some(0) => {
+                let end = self.rest.find('/').unwrap_or(self.rest.len());
+                (Token::Param(&self.rest[1..end]), &self.rest[end..])
+            }
+            Some(start) => (Token::Static(&self.rest[..start]), &self.rest[start..]),
+            None => (Token::Static(self.rest), ""),
+        };
+        self.rest = rest;
+        Some(token)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{tokenize, Token::*};
+
+    #[test]
+    fn static_path() {
+        assert_eq!(tokenize("/").collect::<Vec<_>>(), [Static("/")]);
+        assert_eq!(
+            tokenize("/users/list").collect::<Vec<_>>(),
+            [Static("/users/list")]
+        );
+        assert_eq!(tokenize("").count(), 0);
+    }
+
+    #[test]
+    fn params() {
+        assert_eq!(
+            tokenize("/users/:id").collect::<Vec<_>>(),
+            [Static("/users/"), Param("id")]
+        );
+        assert_eq!(
+            tokenize("/users/:id/posts/:post/").collect::<Vec<_>>(),
+            [
+                Static("/users/"),
+                Param("id"),
+                Static("/posts/"),
+                Param("post"),
+                Static("/")
+            ]
+        );
+        assert_eq!(
+            tokenize("/v:version/x").collect::<Vec<_>>(),
+            [Static("/v"), Param("version"), Static("/x")]
+        );
+    }
+
+    #[test]
+    fn catch_all() {
+        assert_eq!(
+            tokenize("/files/*path").collect::<Vec<_>>(),
+            [Static("/files/"), CatchAll("path")]
+        );
+        assert_eq!(
+            tokenize("/:user/*path").collect::<Vec<_>>(),
+            [Static("/"), Param("user"), Static("/"), CatchAll("path")]
+        );
+
+        // Catch-alls extend to the end of the path, even past other segments
+        assert_eq!(
+            tokenize("/*path/more/:x").collect::<Vec<_>>(),
+            [Static("/"), CatchAll("path/more/:x")]
+        );
+    }
+
+    #[test]
+    fn malformed_params() {
+        assert_eq!(
+            tokenize("/:/x").collect::<Vec<_>>(),
+            [Static("/"), Param(""), Static("/x")]
+        );
+        assert_eq!(
+            tokenize("/:a:b").collect::<Vec<_>>(),
+            [Static("/"), Param("a:b")]
+        );
+        assert_eq!(
+            tokenize("/*").collect::<Vec<_>>(),
+            [Static("/"), CatchAll("")]
+        );
+    }
+}
@@ -0,0 +1,251 @@
+//! RFC 7807 problem details for HTTP APIs
+//!
+//! [`Problem`] is a reply rendered as an `application/problem+json` document, and
+//! [`ProblemJsonFormatter`] renders every error generated by routerman as one.
+//! ```
+//! # use routerman::{
+//! #   method::get, problem::{Problem, ProblemJsonFormatter}, request::Request,
+//! #   router::RouterBuilder,
+//! # };
+//! # use hyper::StatusCode;
+//! RouterBuilder::<ProblemJsonFormatter>::new()
+//!     .route(
+//!         "/account",
+//!         get(|req: Request| async move {
+//!             Err::<String, _>(
+//!                 Problem::new(StatusCode::FORBIDDEN)
+//!                     .type_uri("https://example.com/probs/out-of-credit")
+//!                     .detail("Your current balance is 30, but that costs 50.")
+//!                     .instance(req.uri().path())
+//!                     .extension("balance", 30),
+//!             )
+//!         }),
+//!     )
+//!     .build();
+//! ```
+
+use crate::{
+    mime,
+    request::{InvalidParamEncoding, Request},
+    response::{Formatter, Reply, Response},
+};
+use hyper::{
+    header::{self, HeaderValue},
+    StatusCode, Uri,
+};
+use serde_json::{Map, Value};
+use std::error::Error as StdError;
+
+/// Members defined by the RFC, which can only be set through their dedicated methods
+const RESERVED_MEMBERS: [&str; 5] = ["type", "title", "status", "detail", "instance"];
+
+/// Problem details document (RFC 7807)
+///
+/// The title defaults to the status code's canonical reason and the type to `about:blank`, as
+/// recommended for problems that carry no additional semantics.
+#[derive(Debug, Clone)]
+pub struct Problem {
+    status: StatusCode,
+    type_uri: Option<String>,
+    title: Option<String>,
+    detail: Option<String>,
+    instance: Option<String>,
+    extensions: Map<String, Value>,
+}
+
+impl Problem {
+    pub fn new(status: StatusCode) -> Self {
+        Self {
+            status,
+            type_uri: None,
+            title: None,
+            detail: None,
+            instance: None,
+            extensions: Map::new(),
+        }
+    }
+
+    /// URI identifying the type of the problem
+    pub fn type_uri(mut self, type_uri: impl Into<String>) -> Self {
+        self.type_uri = Some(type_uri.into());
+        self
+    }
+
+    /// Short, human-readable summary of the type of the problem
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    /// Human-readable explanation specific to this occurrence of the problem
+    pub fn detail(mut self, detail: impl Into<String>) -> Self {
+        self.detail = Some(detail.into());
+        self
+    }
+
+    /// URI identifying this occurrence of the problem, usually the request's path
+    pub fn instance(mut self, instance: impl Into<String>) -> Self {
+        self.instance = Some(instance.into());
+        self
+    }
+
+    /// Additional member of the problem document. Members defined by the RFC (`type`, `title`,
+    /// `status`, `detail` and `instance`) are ignored, since they are set through their dedicated
+    /// methods.
+    pub fn extension(mut self, key: impl Into<String>, value: impl Into<Value>) -> Self {
+        let key = key.into();
+        if !RESERVED_MEMBERS.contains(&key.as_str()) {
+            self.extensions.insert(key, value.into());
+        }
+        self
+    }
+
+    fn to_json(&self) -> Value {
+        let mut document = self.extensions.clone();
+        document.insert(
+            "type".into(),
+            self.type_uri.as_deref().unwrap_or("about:blank").into(),
+        );
+        document.insert(
+            "title".into(),
+            match &self.title {
+                Some(title) => title.as_str(),
+                None => self.status.canonical_reason().unwrap_or_default(),
+            }
+            .into(),
+        );
+        document.insert("status".into(), self.status.as_u16().into());
+        if let Some(detail) = &self.detail {
+            document.insert("detail".into(), detail.as_str().into());
+        }
+        if let Some(instance) = &self.instance {
+            document.insert("instance".into(), instance.as_str().into());
+        }
+        Value::Object(document)
+    }
+}
+
+impl<Fmt> Reply<Fmt> for Problem {
+    fn reply(self, fmt: Fmt) -> Response {
+        (
+            self.status,
+            [(
+                header::CONTENT_TYPE,
+                mime::APPLICATION_PROBLEM_JSON.header(),
+            )],
+            self.to_json().to_string().into_bytes(),
+        )
+            .reply(fmt)
+    }
+}
+
+/// Formatter replying to every error with a [`Problem`], using the request's path as the problem's
+/// instance
+///
+/// The error's message is used as the problem's detail, except for server errors (`5xx`), whose
+/// messages might contain sensitive information.
+#[derive(Debug, Clone, Default)]
+pub struct ProblemJsonFormatter {
+    /// Uri of the request being replied to. Cloning it is cheap, unlike copying its path.
+    uri: Option<Uri>,
+}
+
+impl ProblemJsonFormatter {
+    fn problem(&self, status: StatusCode) -> Problem {
+        let problem = Problem::new(status);
+        match &self.uri {
+            Some(uri) => problem.instance(uri.path()),
+            None => problem,
+        }
+    }
+}
+
+impl Formatter for ProblemJsonFormatter {
+    fn for_request(&self, req: &Request) -> Self {
+        Self {
+            uri: Some(req.uri().clone()),
+        }
+    }
+
+    fn not_found(&self, _req: &Request) -> Response {
+        self.problem(StatusCode::NOT_FOUND).reply(self.clone())
+    }
+
+    fn method_not_allowed(&self, allow: &HeaderValue) -> Response {
+        let mut res = self
+            .problem(StatusCode::METHOD_NOT_ALLOWED)
+            .reply(self.clone());
+        res.headers_mut().insert(header::ALLOW, allow.clone());
+        res
+    }
+
+    fn bad_param(&self, err: &InvalidParamEncoding) -> Response {
+        self.format_error(err, StatusCode::BAD_REQUEST)
+    }
+
+    fn format_error(&self, err: &dyn StdError, status: StatusCode) -> Response {
+        let detail = match status.is_server_error() {
+            true => "The server failed to process the request.".to_owned(),
+            false => err.to_string(),
+        };
+        self.problem(status).detail(detail).reply(self.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Problem, ProblemJsonFormatter};
+    use crate::response::Formatter;
+    use hyper::{body, Body, Request, StatusCode};
+    use serde_json::{json, Value};
+    use std::io;
+
+    async fn document(fmt: &ProblemJsonFormatter, err: io::Error, status: StatusCode) -> Value {
+        let res = fmt.format_error(&err, status);
+        let body = body::to_bytes(res.into_body()).await.unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    #[test]
+    fn reserved_members_are_not_extensions() {
+        let problem = Problem::new(StatusCode::FORBIDDEN)
+            .detail("Your current balance is 30, but that costs 50.")
+            .extension("balance", 30)
+            .extension("detail", "overridden")
+            .extension("status", 200)
+            .extension("type", "https://example.com/overridden")
+            .extension("title", "overridden")
+            .extension("instance", "/overridden");
+        assert_eq!(
+            problem.to_json(),
+            json!({
+                "type": "about:blank",
+                "title": "Forbidden",
+                "status": 403,
+                "detail": "Your current balance is 30, but that costs 50.",
+                "balance": 30,
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn server_errors_are_not_exposed() {
+        let req = Request::get("/users/1?page=2").body(Body::empty()).unwrap();
+        let fmt = ProblemJsonFormatter::default().for_request(&req);
+
+        let doc = document(
+            &fmt,
+            io::Error::other("invalid id"),
+            StatusCode::BAD_REQUEST,
+        )
+        .await;
+        assert_eq!(doc["detail"], "invalid id");
+        assert_eq!(doc["instance"], "/users/1");
+
+        let err = io::Error::other("connection to 10.0.0.3 refused");
+        let doc = document(&fmt, err, StatusCode::INTERNAL_SERVER_ERROR).await;
+        assert_eq!(doc["status"], 500);
+        assert_eq!(doc["detail"], "The server failed to process the request.");
+        assert!(!doc.to_string().contains("10.0.0.3"));
+    }
+}
@@ -1,29 +1,71 @@
 use super::params::RouteParams;
+use crate::router::RouteNames;
 use std::{
-    net::SocketAddr,
     ops::{Deref, DerefMut},
+    sync::Arc,
 };
 use thiserror::Error;
 
-pub struct RemoteAddrExt(pub SocketAddr);
+/// Prefix of the nested router that handled the request
+pub struct NestPrefixExt(pub Arc<str>);
 
-impl Deref for RemoteAddrExt {
-    type Target = SocketAddr;
+impl Deref for NestPrefixExt {
+    type Target = str;
 
     fn deref(&self) -> &Self::Target {
         &self.0
     }
 }
 
-impl DerefMut for RemoteAddrExt {
-    fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.0
+/// Route pattern that matched a request
+///
+/// Unlike the request's path, the pattern doesn't contain the values of any parameters, which makes
+/// it suitable for labelling metrics or logs. Available to handlers through
+/// [`RequestExt::matched_path`], or directly from the request's extensions (eg. in a tower layer).
+///
+/// [`RequestExt::matched_path`]: crate::request::RequestExt::matched_path
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum MatchedPath {
+    /// The request was handled by the route registered to this path. Routes of nested routers
+    /// include the prefix they were nested under.
+    Route(Arc<str>),
+
+    /// No route matched and the request was handled by a default route. Contains the prefix of the
+    /// nested router the default route belongs to, if any.
+    Default(Option<Arc<str>>),
+}
+
+impl MatchedPath {
+    /// Path of the matched route, or `None` if the request was handled by a default route
+    pub fn as_route(&self) -> Option<&str> {
+        match self {
+            MatchedPath::Route(path) => Some(path),
+            MatchedPath::Default(_) => None,
+        }
     }
 }
 
-impl From<RemoteAddrExt> for SocketAddr {
-    fn from(ext: RemoteAddrExt) -> Self {
-        ext.0
+/// Shared state provided through [`RouterBuilder::with_state`]
+///
+/// [`RouterBuilder::with_state`]: crate::router::RouterBuilder::with_state
+pub struct StateExt<S>(pub S);
+
+impl<S> Deref for StateExt<S> {
+    type Target = S;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+/// Named routes of the router that handled the request
+pub(crate) struct RouteNamesExt(pub(crate) Arc<RouteNames>);
+
+impl Deref for RouteNamesExt {
+    type Target = RouteNames;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
     }
 }
 
@@ -1,18 +1,33 @@
+use super::state::StateType;
+use crate::response::Reply;
 use futures_util::Future;
 use hyper::{
     body::{Bytes, HttpBody},
-    Request,
+    Body,
 };
-use std::pin::Pin;
+use std::{
+    convert::Infallible,
+    future::{ready, Ready},
+    pin::Pin,
+};
+
+type Request<B = Body> = hyper::Request<B>;
+type BoxFuture<'a, Out> = Pin<Box<dyn Future<Output = Out> + Send + 'a>>;
 
 pub trait ExtractFrom<Req: ?Sized>: Sized {
     type Error;
     type Future: Future<Output = Result<Self, Self::Error>>;
 
     fn extract_from(req: Req) -> Self::Future;
+
+    /// Shared state the extractor expects the router to provide. Routers fail to build if a route
+    /// requires state that was not provided to them.
+    fn required_state() -> Option<StateType> {
+        None
+    }
 }
 
-impl<'a, B> ExtractFrom<Request<B>> for Bytes
+impl<B> ExtractFrom<Request<B>> for Bytes
 where
     B: HttpBody + Send + 'static,
     B::Data: Send,
@@ -21,6 +36,107 @@ where
     type Future = Pin<Box<dyn Future<Output = Result<Self, Self::Error>> + Send>>;
 
     fn extract_from(req: Request<B>) -> Self::Future {
-        Box::pin(async move { Ok(hyper::body::to_bytes(req.into_body()).await?) })
+        Box::pin(hyper::body::to_bytes(req.into_body()))
+    }
+}
+
+impl ExtractFrom<Request> for Request {
+    type Error = Infallible;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn extract_from(req: Request) -> Self::Future {
+        ready(Ok(req))
+    }
+}
+
+/// Handler argument that can be extracted from a borrowed request. Any argument of a handler can be
+/// extracted this way.
+///
+/// Implemented for every type that implements [`ExtractFrom<&Request>`], with its error used as the
+/// rejection.
+pub trait FromRequestParts<Fmt>: Sized {
+    /// Reply sent in place of the handler's response when extraction fails
+    type Rejection: Reply<Fmt>;
+
+    fn from_request_parts(req: &Request) -> BoxFuture<'_, Result<Self, Self::Rejection>>;
+
+    /// Shared state the extractor expects the router to provide. See
+    /// [`ExtractFrom::required_state`].
+    fn required_state() -> Option<StateType> {
+        None
+    }
+}
+
+impl<T, E, Fmt> FromRequestParts<Fmt> for T
+where
+    T: for<'a> ExtractFrom<&'a Request, Error = E> + Send + 'static,
+    for<'a> <T as ExtractFrom<&'a Request>>::Future: Send,
+    E: Reply<Fmt>,
+{
+    type Rejection = E;
+
+    fn from_request_parts(req: &Request) -> BoxFuture<'_, Result<Self, Self::Rejection>> {
+        Box::pin(T::extract_from(req))
+    }
+
+    fn required_state() -> Option<StateType> {
+        <T as ExtractFrom<&'static Request>>::required_state()
+    }
+}
+
+/// Handler argument that consumes the request. Only the last argument of a handler can be extracted
+/// this way.
+///
+/// Implemented for every type that implements [`ExtractFrom<Request>`] or [`FromRequestParts`].
+///
+/// Note: The M type argument is only there to allow both of the above implementations to exist.
+pub trait FromRequest<Fmt, M = ViaRequest>: Sized {
+    /// Reply sent in place of the handler's response when extraction fails
+    type Rejection: Reply<Fmt>;
+
+    fn from_request(req: Request) -> BoxFuture<'static, Result<Self, Self::Rejection>>;
+
+    /// Shared state the extractor expects the router to provide. See
+    /// [`ExtractFrom::required_state`].
+    fn required_state() -> Option<StateType> {
+        None
+    }
+}
+
+#[doc(hidden)]
+pub struct ViaRequest;
+
+#[doc(hidden)]
+pub struct ViaParts;
+
+impl<T, Fmt> FromRequest<Fmt, ViaRequest> for T
+where
+    T: ExtractFrom<Request> + Send,
+    T::Future: Send + 'static,
+    T::Error: Reply<Fmt>,
+{
+    type Rejection = T::Error;
+
+    fn from_request(req: Request) -> BoxFuture<'static, Result<Self, Self::Rejection>> {
+        Box::pin(T::extract_from(req))
+    }
+
+    fn required_state() -> Option<StateType> {
+        <T as ExtractFrom<Request>>::required_state()
+    }
+}
+
+impl<T, Fmt> FromRequest<Fmt, ViaParts> for T
+where
+    T: FromRequestParts<Fmt>,
+{
+    type Rejection = T::Rejection;
+
+    fn from_request(req: Request) -> BoxFuture<'static, Result<Self, Self::Rejection>> {
+        Box::pin(async move { T::from_request_parts(&req).await })
+    }
+
+    fn required_state() -> Option<StateType> {
+        <T as FromRequestParts<Fmt>>::required_state()
     }
 }
@@ -0,0 +1,461 @@
+//! Resolution of the original client through the headers added by trusted proxies
+
+use super::Request;
+use crate::connection::ConnectionInfo;
+use hyper::header::{HeaderMap, HeaderName, FORWARDED};
+use std::{
+    fmt,
+    net::{IpAddr, Ipv6Addr, SocketAddr},
+    str::FromStr,
+};
+use thiserror::Error;
+
+const X_FORWARDED_FOR: HeaderName = HeaderName::from_static("x-forwarded-for");
+const X_FORWARDED_PROTO: HeaderName = HeaderName::from_static("x-forwarded-proto");
+const X_FORWARDED_HOST: HeaderName = HeaderName::from_static("x-forwarded-host");
+const X_REAL_IP: HeaderName = HeaderName::from_static("x-real-ip");
+
+/// Header a trusted proxy reports the original client through. Only the configured header is read,
+/// any other is ignored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProxyHeader {
+    /// RFC 7239 `Forwarded` header, along with the scheme and host of each element
+    Forwarded,
+
+    /// `X-Forwarded-For` header. The scheme and host are taken from the `X-Forwarded-Proto` and
+    /// `X-Forwarded-Host` values recorded by the same proxy as the client.
+    XForwardedFor,
+
+    /// `X-Real-IP` header, set by a single proxy. The scheme and host are taken from the last
+    /// `X-Forwarded-Proto` and `X-Forwarded-Host` values.
+    XRealIp,
+}
+
+/// Range of IP addresses (eg. `10.0.0.0/8` or `2001:db8::/32`)
+///
+/// Parsed from an address and a prefix length, or from a single address, which only contains
+/// itself.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct IpNetwork {
+    addr: IpAddr,
+    prefix_len: u8,
+}
+
+impl IpNetwork {
+    /// Network of the addresses sharing the first `prefix_len` bits of `addr`
+    pub fn new(addr: IpAddr, prefix_len: u8) -> Result<Self, InvalidIpNetwork> {
+        let max_len = match addr {
+            IpAddr::V4(_) => 32,
+            IpAddr::V6(_) => 128,
+        };
+        match prefix_len <= max_len {
+            true => Ok(Self { addr, prefix_len }),
+            false => Err(InvalidIpNetwork),
+        }
+    }
+
+    pub fn addr(&self) -> IpAddr {
+        self.addr
+    }
+
+    pub fn prefix_len(&self) -> u8 {
+        self.prefix_len
+    }
+
+    /// Whether an address belongs to the network. Addresses of the other IP version never do.
+    pub fn contains(&self, addr: &IpAddr) -> bool {
+        match (self.addr, addr) {
+            (IpAddr::V4(net), IpAddr::V4(addr)) => {
+                let mask = u32::MAX.checked_shl(32 - u32::from(self.prefix_len));
+                let mask = mask.unwrap_or(0);
+                u32::from(net) & mask == u32::from(*addr) & mask
+            }
+            (IpAddr::V6(net), IpAddr::V6(addr)) => {
+                let mask = u128::MAX.checked_shl(128 - u32::from(self.prefix_len));
+                let mask = mask.unwrap_or(0);
+                u128::from(net) & mask == u128::from(*addr) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+impl From<IpAddr> for IpNetwork {
+    fn from(addr: IpAddr) -> Self {
+        let prefix_len = match addr {
+            IpAddr::V4(_) => 32,
+            IpAddr::V6(_) => 128,
+        };
+        Self { addr, prefix_len }
+    }
+}
+
+impl FromStr for IpNetwork {
+    type Err = InvalidIpNetwork;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once('/') {
+            Some((addr, prefix_len)) => {
+                let addr = addr.parse().map_err(|_| InvalidIpNetwork)?;
+                // Only plain decimal lengths, u8's parser also accepts a leading `+`
+                if !prefix_len.bytes().all(|b| b.is_ascii_digit()) {
+                    return Err(InvalidIpNetwork);
+                }
+                Self::new(addr, prefix_len.parse().map_err(|_| InvalidIpNetwork)?)
+            }
+            None => s
+                .parse::<IpAddr>()
+                .map(Self::from)
+                .map_err(|_| InvalidIpNetwork),
+        }
+    }
+}
+
+impl fmt::Display for IpNetwork {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.addr, self.prefix_len)
+    }
+}
+
+#[derive(Debug, Error)]
+#[error("invalid ip network")]
+pub struct InvalidIpNetwork;
+
+/// Original client of a request that arrived through a trusted proxy
+pub(crate) struct ForwardedExt {
+    /// `None` if the proxies reported the client as unknown or obfuscated
+    pub(crate) addr: Option<IpAddr>,
+    pub(crate) scheme: Option<String>,
+    pub(crate) host: Option<String>,
+}
+
+impl ForwardedExt {
+    /// Resolve the original client of a request, if it arrived through a trusted proxy. The
+    /// client is reported through the header configured for the peer's network.
+    pub(crate) fn resolve(req: &Request, trusted: &[(IpNetwork, ProxyHeader)]) -> Option<Self> {
+        let is_trusted = |addr: &IpAddr| {
+            let addr = addr.to_canonical();
+            trusted.iter().any(|(net, _)| net.contains(&addr))
+        };
+
+        let peer = req
+            .extensions()
+            .get::<ConnectionInfo>()
+            .and_then(ConnectionInfo::remote_addr)
+            .map(|addr| addr.ip().to_canonical())?;
+        let (_, header) = trusted.iter().find(|(net, _)| net.contains(&peer))?;
+
+        let headers = req.headers();
+        match header {
+            ProxyHeader::Forwarded => Some(Self::from_forwarded(headers, peer, is_trusted)),
+            ProxyHeader::XForwardedFor => {
+                // Walk the hops from the closest to the furthest, stopping at the first one that
+                // is not trusted. If every hop is trusted, the furthest one is the client, and
+                // without any hop the proxy itself is.
+                let hops = header_list(headers, &X_FORWARDED_FOR).collect::<Vec<_>>();
+                let mut client = Some(peer);
+                let mut hop_index = 0;
+                for (index, hop) in hops.into_iter().rev().enumerate() {
+                    client = parse_node(hop);
+                    hop_index = index;
+                    match client {
+                        Some(addr) if is_trusted(&addr) => continue,
+                        _ => break,
+                    }
+                }
+                Some(Self::with_forwarded_proto(headers, client, hop_index))
+            }
+            ProxyHeader::XRealIp => {
+                let client = match header_list(headers, &X_REAL_IP).last() {
+                    Some(real_ip) => parse_node(real_ip),
+                    None => Some(peer),
+                };
+                Some(Self::with_forwarded_proto(headers, client, 0))
+            }
+        }
+    }
+
+    /// Client whose scheme and host are the `X-Forwarded-Proto` and `X-Forwarded-Host` values
+    /// recorded by the same proxy, at `hop_index` from the closest proxy
+    fn with_forwarded_proto(headers: &HeaderMap, addr: Option<IpAddr>, hop_index: usize) -> Self {
+        let hop = |name| {
+            let values = header_list(headers, name).collect::<Vec<_>>();
+            values.into_iter().rev().nth(hop_index).map(str::to_owned)
+        };
+        Self {
+            addr,
+            scheme: hop(&X_FORWARDED_PROTO),
+            host: hop(&X_FORWARDED_HOST),
+        }
+    }
+
+    /// Resolve the client through an RFC 7239 `Forwarded` header. The scheme and host are taken
+    /// from the same element as the client, since they were recorded by the proxy it connected to.
+    fn from_forwarded(
+        headers: &HeaderMap,
+        peer: IpAddr,
+        is_trusted: impl Fn(&IpAddr) -> bool,
+    ) -> Self {
+        let elements = header_list(headers, &FORWARDED).collect::<Vec<_>>();
+
+        let mut client = Self {
+            addr: Some(peer),
+            scheme: None,
+            host: None,
+        };
+        for element in elements.into_iter().rev() {
+            client = Self::parse_element(element);
+            match client.addr {
+                Some(addr) if is_trusted(&addr) => continue,
+                _ => break,
+            }
+        }
+        client
+    }
+
+    /// Parse a single element of a `Forwarded` header (eg. `for=192.0.2.60;proto=http`)
+    fn parse_element(element: &str) -> Self {
+        let mut parsed = Self {
+            addr: None,
+            scheme: None,
+            host: None,
+        };
+        for pair in split_quoted(element, ';') {
+            let Some((key, value)) = pair.split_once('=') else {
+                continue;
+            };
+            let value = unquote(value.trim());
+            match key.trim().to_ascii_lowercase().as_str() {
+                "for" => parsed.addr = parse_node(value),
+                "proto" => parsed.scheme = Some(value.to_ascii_lowercase()),
+                "host" => parsed.host = Some(value.to_owned()),
+                _ => {}
+            }
+        }
+        parsed
+    }
+}
+
+/// Comma-separated values of every occurrence of a header, in order. Values that are not valid
+/// utf-8 are skipped.
+fn header_list<'a>(headers: &'a HeaderMap, name: &HeaderName) -> impl Iterator<Item = &'a str> {
+    headers
+        .get_all(name)
+        .into_iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| split_quoted(value, ','))
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+}
+
+/// Split a string on a delimiter, ignoring delimiters inside quoted strings
+fn split_quoted(value: &str, delimiter: char) -> impl Iterator<Item = &str> {
+    let mut quoted = false;
+    let mut escaped = false;
+    value.split(move |c| {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if quoted => escaped = true,
+            '"' => quoted = !quoted,
+            _ => return c == delimiter && !quoted,
+        }
+        false
+    })
+}
+
+fn unquote(value: &str) -> &str {
+    value
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+        .unwrap_or(value)
+}
+
+/// Parse a node identifier (eg. `192.0.2.43`, `192.0.2.43:47011` or `[2001:db8::17]:4711`). Unknown
+/// and obfuscated identifiers yield `None`.
+fn parse_node(node: &str) -> Option<IpAddr> {
+    let node = unquote(node);
+    if let Some(rest) = node.strip_prefix('[') {
+        let (addr, _) = rest.split_once(']')?;
+        return addr.parse::<Ipv6Addr>().ok().map(IpAddr::V6);
+    }
+    node.parse::<IpAddr>()
+        .or_else(|_| node.parse::<SocketAddr>().map(|addr| addr.ip()))
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ForwardedExt, IpNetwork, ProxyHeader};
+    use crate::{connection::ConnectionInfo, request::Request};
+    use hyper::Body;
+    use std::net::IpAddr;
+
+    fn request(peer: &str, headers: &[(&str, &str)]) -> Request {
+        let mut req =
+            hyper::Request::builder().extension(ConnectionInfo::Tcp(peer.parse().unwrap()));
+        for (name, value) in headers {
+            req = req.header(*name, *value);
+        }
+        req.body(Body::empty()).unwrap()
+    }
+
+    fn resolve(header: ProxyHeader, peer: &str, headers: &[(&str, &str)]) -> Option<ForwardedExt> {
+        let trusted = [("10.0.0.0/8".parse().unwrap(), header)];
+        ForwardedExt::resolve(&request(peer, headers), &trusted)
+    }
+
+    fn client(header: ProxyHeader, headers: &[(&str, &str)]) -> Option<IpAddr> {
+        resolve(header, "10.0.0.2:4000", headers).unwrap().addr
+    }
+
+    fn ip(addr: &str) -> Option<IpAddr> {
+        Some(addr.parse().unwrap())
+    }
+
+    #[test]
+    fn networks() {
+        let net = "10.0.0.0/8".parse::<IpNetwork>().unwrap();
+        assert!(net.contains(&"10.255.0.1".parse().unwrap()));
+        assert!(!net.contains(&"11.0.0.1".parse().unwrap()));
+        assert!(!net.contains(&"::ffff:10.0.0.1".parse().unwrap()));
+        assert_eq!(net.to_string(), "10.0.0.0/8");
+
+        let net = "2001:db8::/32".parse::<IpNetwork>().unwrap();
+        assert!(net.contains(&"2001:db8:1::1".parse().unwrap()));
+        assert!(!net.contains(&"2001:db9::1".parse().unwrap()));
+
+        let any = "0.0.0.0/0".parse::<IpNetwork>().unwrap();
+        assert!(any.contains(&"203.0.113.7".parse().unwrap()));
+        let host = "192.0.2.1".parse::<IpNetwork>().unwrap();
+        assert_eq!(host.prefix_len(), 32);
+        assert!(!host.contains(&"192.0.2.2".parse().unwrap()));
+
+        for invalid in [
+            "10.0.0.0/33",
+            "::/129",
+            "10.0.0.0/",
+            "10.0.0.0/+8",
+            "10.0.0/8",
+            "x",
+        ] {
+            assert!(invalid.parse::<IpNetwork>().is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn quoted_ipv6_with_port() {
+        let forwarded = r#"for="[2001:db8::17]:4711";proto=https;host=example.com"#;
+        let client = resolve(
+            ProxyHeader::Forwarded,
+            "10.0.0.2:4000",
+            &[("forwarded", forwarded)],
+        )
+        .unwrap();
+        assert_eq!(client.addr, ip("2001:db8::17"));
+        assert_eq!(client.scheme.as_deref(), Some("https"));
+        assert_eq!(client.host.as_deref(), Some("example.com"));
+    }
+
+    #[test]
+    fn unknown_and_obfuscated_clients() {
+        for forwarded in ["for=unknown", "for=_hidden, for=10.0.0.1", r#"for="_x:_y""#] {
+            let headers = [("forwarded", forwarded)];
+            assert_eq!(
+                client(ProxyHeader::Forwarded, &headers),
+                None,
+                "{forwarded}"
+            );
+        }
+        let headers = [("x-forwarded-for", "unknown")];
+        assert_eq!(client(ProxyHeader::XForwardedFor, &headers), None);
+    }
+
+    #[test]
+    fn every_hop_trusted() {
+        let headers = [("x-forwarded-for", "10.0.0.9, 10.0.0.1")];
+        assert_eq!(client(ProxyHeader::XForwardedFor, &headers), ip("10.0.0.9"));
+
+        let headers = [("forwarded", "for=10.0.0.9;proto=https, for=10.0.0.1")];
+        assert_eq!(client(ProxyHeader::Forwarded, &headers), ip("10.0.0.9"));
+
+        // A trusted proxy without the header is the client itself
+        assert_eq!(client(ProxyHeader::XForwardedFor, &[]), ip("10.0.0.2"));
+    }
+
+    #[test]
+    fn unparseable_hop() {
+        // The walk stops at the unparseable hop, rather than trusting the hops beyond it
+        let headers = [("x-forwarded-for", "203.0.113.7, not-an-ip, 10.0.0.1")];
+        assert_eq!(client(ProxyHeader::XForwardedFor, &headers), None);
+
+        let headers = [("x-real-ip", "not-an-ip")];
+        assert_eq!(client(ProxyHeader::XRealIp, &headers), None);
+    }
+
+    #[test]
+    fn ipv4_mapped_peer() {
+        let headers = [("x-forwarded-for", "203.0.113.7, ::ffff:10.0.0.1")];
+        let client = resolve(
+            ProxyHeader::XForwardedFor,
+            "[::ffff:10.0.0.2]:4000",
+            &headers,
+        );
+        assert_eq!(client.unwrap().addr, ip("203.0.113.7"));
+    }
+
+    #[test]
+    fn untrusted_peer() {
+        let headers = [
+            ("x-forwarded-for", "203.0.113.7"),
+            ("x-forwarded-proto", "https"),
+        ];
+        assert!(resolve(ProxyHeader::XForwardedFor, "192.0.2.1:4000", &headers).is_none());
+    }
+
+    #[test]
+    fn spoofed_headers() {
+        // Addresses the client sent itself are before the one the proxy added
+        let headers = [("x-forwarded-for", "198.51.100.1, 203.0.113.7")];
+        assert_eq!(
+            client(ProxyHeader::XForwardedFor, &headers),
+            ip("203.0.113.7")
+        );
+
+        // Headers other than the configured one are ignored, even when the proxy doesn't strip them
+        let headers = [
+            ("forwarded", "for=198.51.100.1"),
+            ("x-real-ip", "198.51.100.1"),
+            ("x-forwarded-for", "203.0.113.7"),
+        ];
+        assert_eq!(
+            client(ProxyHeader::XForwardedFor, &headers),
+            ip("203.0.113.7")
+        );
+        let headers = [
+            ("x-forwarded-for", "198.51.100.1"),
+            ("forwarded", "for=203.0.113.7"),
+        ];
+        assert_eq!(client(ProxyHeader::Forwarded, &headers), ip("203.0.113.7"));
+    }
+
+    #[test]
+    fn proto_and_host_follow_the_client_hop() {
+        let headers = [
+            ("x-forwarded-for", "203.0.113.7, 10.0.0.1"),
+            ("x-forwarded-proto", "https, http"),
+            ("x-forwarded-host", "example.com, internal"),
+        ];
+        let client = resolve(ProxyHeader::XForwardedFor, "10.0.0.2:4000", &headers).unwrap();
+        assert_eq!(client.addr, ip("203.0.113.7"));
+        assert_eq!(client.scheme.as_deref(), Some("https"));
+        assert_eq!(client.host.as_deref(), Some("example.com"));
+
+        // The proxy the client connected to didn't record a scheme
+        let headers = [
+            ("x-forwarded-for", "203.0.113.7, 10.0.0.1"),
+            ("x-forwarded-proto", "http"),
+        ];
+        let client = resolve(ProxyHeader::XForwardedFor, "10.0.0.2:4000", &headers).unwrap();
+        assert_eq!(client.scheme, None);
+    }
+}
@@ -1,38 +1,193 @@
-use std::net::SocketAddr;
+use std::net::{IpAddr, SocketAddr};
 
 use hyper::Body;
 
+pub use self::ext::{InvalidParamEncoding, MatchedPath};
+
 use self::{
-    ext::{RemoteAddrExt, RouteParamsExt},
+    ext::{NestPrefixExt, RouteNamesExt, RouteParamsExt, StateExt},
+    forwarded::ForwardedExt,
     params::RouteParams,
 };
+use crate::{connection::ConnectionInfo, router::UrlForError};
 
 pub(crate) mod ext;
+pub(crate) mod forwarded;
 pub(crate) mod params;
 
 pub mod extract;
 
+pub mod state;
+
+#[cfg(feature = "path")]
+pub mod path;
+
+#[cfg(feature = "query")]
+pub mod query;
+
 pub type Request = hyper::Request<Body>;
 
 pub trait RequestExt {
     fn params(&self) -> &RouteParams;
+
+    /// Connection the request arrived through
+    fn connection_info(&self) -> &ConnectionInfo;
+
+    /// Address of the peer
+    ///
+    /// # Panics
+    /// Panics if the request didn't arrive through a TCP connection.
+    #[deprecated(
+        note = "use `connection_info().remote_addr()`, which supports any connection type"
+    )]
     fn remote_address(&self) -> &SocketAddr;
+
+    /// Route pattern that matched the request (eg. `/users/:id`)
+    fn matched_path(&self) -> &MatchedPath;
+
+    /// Prefix of the nested router that handled the request, if any
+    fn nest_prefix(&self) -> Option<&str>;
+
+    /// Request path, relative to the prefix of the nested router that handled the request
+    fn relative_path(&self) -> &str;
+
+    /// Shared state provided through [`RouterBuilder::with_state`]
+    ///
+    /// [`RouterBuilder::with_state`]: crate::router::RouterBuilder::with_state
+    fn state<S: Send + Sync + 'static>(&self) -> &S;
+
+    /// Generate the path of a named route of the router that handled the request. See
+    /// [`Router::url_for`].
+    ///
+    /// [`Router::url_for`]: crate::router::Router::url_for
+    fn url_for(&self, name: &str, params: &[(&str, &str)]) -> Result<String, UrlForError>;
+
+    /// Address of the client, as reported by the trusted proxies the request went through. See
+    /// [`RouterBuilder::trusted_proxy`].
+    ///
+    /// Requests that didn't arrive through a trusted proxy are attributed to the peer. Returns
+    /// `None` if the peer has no IP address, or if the proxies reported the client as unknown.
+    ///
+    /// [`RouterBuilder::trusted_proxy`]: crate::router::RouterBuilder::trusted_proxy
+    fn client_addr(&self) -> Option<IpAddr>;
+
+    /// Scheme the client used (eg. `https`), as reported by the trusted proxies the request went
+    /// through. Defaults to the scheme of the connection.
+    fn client_scheme(&self) -> &str;
+
+    /// Host the client requested, as reported by the trusted proxies the request went through.
+    /// Defaults to the `Host` header.
+    fn client_host(&self) -> Option<&str>;
+
+    /// Details of the TLS session the request arrived through, if any
+    #[cfg(feature = "tls")]
+    fn tls_info(&self) -> Option<&crate::tls::TlsInfo>;
 }
 
 impl RequestExt for Request {
     #[track_caller]
     fn params(&self) -> &RouteParams {
-        &*self
-            .extensions()
+        self.extensions()
             .get::<RouteParamsExt>()
             .expect("missing request parameters (request not processed by routerman?)")
     }
 
+    #[track_caller]
+    fn connection_info(&self) -> &ConnectionInfo {
+        self.extensions()
+            .get::<ConnectionInfo>()
+            .expect("missing connection info (request not processed by routerman?)")
+    }
+
     #[track_caller]
     fn remote_address(&self) -> &SocketAddr {
-        &*self
+        self.connection_info()
+            .remote_addr()
+            .expect("missing remote address (request not received through tcp?)")
+    }
+
+    #[track_caller]
+    fn matched_path(&self) -> &MatchedPath {
+        self.extensions()
+            .get::<MatchedPath>()
+            .expect("missing matched path (request not processed by routerman?)")
+    }
+
+    fn nest_prefix(&self) -> Option<&str> {
+        self.extensions().get::<NestPrefixExt>().map(|ext| &**ext)
+    }
+
+    fn relative_path(&self) -> &str {
+        let path = self.uri().path();
+        match self
+            .nest_prefix()
+            .and_then(|prefix| path.strip_prefix(prefix))
+        {
+            Some("") => "/",
+            Some(relative) => relative,
+            None => path,
+        }
+    }
+
+    #[track_caller]
+    fn state<S: Send + Sync + 'static>(&self) -> &S {
+        self.extensions()
+            .get::<StateExt<S>>()
+            .expect("missing state (state not provided to the router?)")
+    }
+
+    #[track_caller]
+    fn url_for(&self, name: &str, params: &[(&str, &str)]) -> Result<String, UrlForError> {
+        self.extensions()
+            .get::<RouteNamesExt>()
+            .expect("missing route names (request not processed by routerman?)")
+            .url_for(name, params)
+    }
+
+    fn client_addr(&self) -> Option<IpAddr> {
+        match self.extensions().get::<ForwardedExt>() {
+            Some(forwarded) => forwarded.addr,
+            None => self
+                .extensions()
+                .get::<ConnectionInfo>()
+                .and_then(ConnectionInfo::remote_addr)
+                .map(SocketAddr::ip),
+        }
+    }
+
+    fn client_scheme(&self) -> &str {
+        if let Some(scheme) = self
+            .extensions()
+            .get::<ForwardedExt>()
+            .and_then(|forwarded| forwarded.scheme.as_deref())
+        {
+            return scheme;
+        }
+
+        #[cfg(feature = "tls")]
+        if self.tls_info().is_some() {
+            return "https";
+        }
+        self.uri().scheme_str().unwrap_or("http")
+    }
+
+    fn client_host(&self) -> Option<&str> {
+        if let Some(host) = self
             .extensions()
-            .get::<RemoteAddrExt>()
-            .expect("missing remote address (request not processed by routerman?)")
+            .get::<ForwardedExt>()
+            .and_then(|forwarded| forwarded.host.as_deref())
+        {
+            return Some(host);
+        }
+
+        self.headers()
+            .get(hyper::header::HOST)
+            .and_then(|host| host.to_str().ok())
+            .or_else(|| self.uri().host())
+    }
+
+    #[cfg(feature = "tls")]
+    fn tls_info(&self) -> Option<&crate::tls::TlsInfo> {
+        self.extensions().get()
     }
 }
@@ -1,15 +1,32 @@
-use std::{collections::HashMap, fmt::Debug};
+use std::fmt::Debug;
 
-pub struct RouteParams(pub(crate) HashMap<Box<str>, Box<str>>);
+/// Decoded route parameters, in the order they appear in the route's path
+pub struct RouteParams(pub(crate) Vec<(Box<str>, Box<str>)>);
 
 impl RouteParams {
     pub fn get(&self, param: impl AsRef<str>) -> Option<&str> {
-        self.0.get(param.as_ref()).map(|v| &**v)
+        let param = param.as_ref();
+        self.0
+            .iter()
+            .find(|(k, _)| &**k == param)
+            .map(|(_, v)| &**v)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.0.iter().map(|(k, v)| (&**k, &**v))
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
     }
 }
 
 impl Debug for RouteParams {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.debug_map().entries(self.0.iter()).finish()
+        f.debug_map().entries(self.iter()).finish()
     }
 }
@@ -0,0 +1,597 @@
+//! Typed route parameters
+
+use super::{ext::RouteParamsExt, extract::ExtractFrom, Request};
+use crate::response::{Formatter, Reply, Response};
+use hyper::StatusCode;
+use serde::{
+    de::{self, DeserializeOwned, DeserializeSeed, IntoDeserializer, Visitor},
+    forward_to_deserialize_any, Deserializer,
+};
+use std::{
+    fmt::Display,
+    future::{ready, Ready},
+};
+use thiserror::Error;
+
+/// Route parameters, deserialized into `T`
+///
+/// Parameters can be deserialized into:
+/// - A struct or map, using the parameters' names as keys.
+/// - A tuple or sequence, in the order the parameters appear in the route's path.
+/// - A single value, if the route has exactly one parameter.
+/// ```
+/// # use routerman::{method::get, request::path::Path, router::Router};
+/// # use serde::Deserialize;
+/// #[derive(Deserialize)]
+/// struct Post {
+///     user: String,
+///     id: u32,
+/// }
+///
+/// let router: Router = Router::builder()
+///     .route("/posts/:id", get(|Path(id): Path<u32>| async move { format!("{}", id) }))
+///     .route(
+///         "/users/:user/posts/:id",
+///         get(|Path(post): Path<Post>| async move { format!("{}: {}", post.user, post.id) }),
+///     )
+///     .build();
+/// ```
+#[derive(Debug, Clone)]
+pub struct Path<T>(
+    /// The deserialized parameters
+    pub T,
+);
+
+impl<'a, T> ExtractFrom<&'a Request> for Path<T>
+where
+    T: DeserializeOwned,
+{
+    type Error = PathError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn extract_from(req: &'a Request) -> Self::Future {
+        let params = match req.extensions().get::<RouteParamsExt>() {
+            Some(params) => &(**params).0[..],
+            None => &[],
+        };
+
+        ready(T::deserialize(ParamsDeserializer { params }).map(Path))
+    }
+}
+
+/// Error deserializing route parameters
+#[derive(Debug, Error)]
+pub enum PathError {
+    /// A parameter's value could not be deserialized
+    #[error("invalid value for param `{key}`: {message}")]
+    InvalidValue { key: Box<str>, message: String },
+
+    /// The number of parameters did not match the number of expected values
+    #[error("expected {expected} params, found {found}")]
+    WrongCount { expected: usize, found: usize },
+
+    /// Any other deserialization error
+    #[error("{0}")]
+    Message(String),
+}
+
+impl PathError {
+    /// Attribute an error to a parameter, unless it is already attributed to one
+    fn for_key(self, key: &str) -> Self {
+        match self {
+            PathError::Message(message) => PathError::InvalidValue {
+                key: Box::from(key),
+                message,
+            },
+            err => err,
+        }
+    }
+}
+
+impl de::Error for PathError {
+    fn custom<T: Display>(msg: T) -> Self {
+        PathError::Message(msg.to_string())
+    }
+}
+
+impl<F: Formatter> Reply<F> for PathError {
+    fn reply(self, fmt: F) -> Response {
+        fmt.format_error(&self, StatusCode::BAD_REQUEST)
+    }
+}
+
+/// Deserializer for all the parameters of a route
+struct ParamsDeserializer<'de> {
+    params: &'de [(Box<str>, Box<str>)],
+}
+
+impl<'de> ParamsDeserializer<'de> {
+    /// Get the only parameter, for deserializing a single value
+    fn single(&self) -> Result<ValueDeserializer<'de>, PathError> {
+        match self.params {
+            [(key, value)] => Ok(ValueDeserializer { key, value }),
+            params => Err(PathError::WrongCount {
+                expected: 1,
+                found: params.len(),
+            }),
+        }
+    }
+}
+
+macro_rules! forward_to_single {
+    ($($method:ident)*) => {
+        $(
+            fn $method<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+            where
+                V: Visitor<'de>,
+            {
+                let value = self.single()?;
+                value.$method(visitor).map_err(|err| err.for_key(value.key))
+            }
+        )*
+    };
+}
+
+impl<'de> Deserializer<'de> for ParamsDeserializer<'de> {
+    type Error = PathError;
+
+    forward_to_single! {
+        deserialize_bool
+        deserialize_i8 deserialize_i16 deserialize_i32 deserialize_i64 deserialize_i128
+        deserialize_u8 deserialize_u16 deserialize_u32 deserialize_u64 deserialize_u128
+        deserialize_f32 deserialize_f64
+        deserialize_char deserialize_str deserialize_string
+        deserialize_bytes deserialize_byte_buf
+        deserialize_identifier
+    }
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_map(visitor)
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self.params {
+            [] => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_unit<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self.params {
+            [] => visitor.visit_unit(),
+            params => Err(PathError::WrongCount {
+                expected: 0,
+                found: params.len(),
+            }),
+        }
+    }
+
+    fn deserialize_unit_struct<V>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_unit(visitor)
+    }
+
+    fn deserialize_newtype_struct<V>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_seq(ParamsAccess {
+            params: self.params.iter(),
+            value: None,
+        })
+    }
+
+    fn deserialize_tuple<V>(self, len: usize, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        if self.params.len() != len {
+            return Err(PathError::WrongCount {
+                expected: len,
+                found: self.params.len(),
+            });
+        }
+
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V>(
+        self,
+        _name: &'static str,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_tuple(len, visitor)
+    }
+
+    fn deserialize_map<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_map(ParamsAccess {
+            params: self.params.iter(),
+            value: None,
+        })
+    }
+
+    fn deserialize_struct<V>(
+        self,
+        _name: &'static str,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_map(visitor)
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let value = self.single()?;
+        value
+            .deserialize_enum(name, variants, visitor)
+            .map_err(|err| err.for_key(value.key))
+    }
+
+    fn deserialize_ignored_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_unit()
+    }
+}
+
+/// Sequence and map access over the parameters of a route
+struct ParamsAccess<'de> {
+    params: std::slice::Iter<'de, (Box<str>, Box<str>)>,
+
+    /// The parameter whose key was just deserialized, when accessed as a map
+    value: Option<ValueDeserializer<'de>>,
+}
+
+impl<'de> de::SeqAccess<'de> for ParamsAccess<'de> {
+    type Error = PathError;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, Self::Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        match self.params.next() {
+            Some((key, value)) => seed
+                .deserialize(ValueDeserializer { key, value })
+                .map(Some)
+                .map_err(|err| err.for_key(key)),
+            None => Ok(None),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.params.len())
+    }
+}
+
+impl<'de> de::MapAccess<'de> for ParamsAccess<'de> {
+    type Error = PathError;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, Self::Error>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        match self.params.next() {
+            Some((key, value)) => {
+                self.value = Some(ValueDeserializer { key, value });
+                seed.deserialize(KeyDeserializer { key }).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, Self::Error>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let value = self
+            .value
+            .take()
+            .ok_or_else(|| PathError::Message("value requested before key".into()))?;
+
+        seed.deserialize(value)
+            .map_err(|err| err.for_key(value.key))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.params.len())
+    }
+}
+
+/// Deserializer for the name of a parameter
+struct KeyDeserializer<'de> {
+    key: &'de str,
+}
+
+impl<'de> Deserializer<'de> for KeyDeserializer<'de> {
+    type Error = PathError;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_borrowed_str(self.key)
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+/// Deserializer for the value of a single parameter
+#[derive(Clone, Copy)]
+struct ValueDeserializer<'de> {
+    key: &'de str,
+    value: &'de str,
+}
+
+macro_rules! parse_value {
+    ($($method:ident => $visit:ident),* $(,)?) => {
+        $(
+            fn $method<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+            where
+                V: Visitor<'de>,
+            {
+                match self.value.parse() {
+                    Ok(value) => visitor.$visit(value),
+                    Err(err) => Err(PathError::InvalidValue {
+                        key: Box::from(self.key),
+                        message: format!("{} (found `{}`)", err, self.value),
+                    }),
+                }
+            }
+        )*
+    };
+}
+
+impl<'de> Deserializer<'de> for ValueDeserializer<'de> {
+    type Error = PathError;
+
+    parse_value! {
+        deserialize_bool => visit_bool,
+        deserialize_i8 => visit_i8,
+        deserialize_i16 => visit_i16,
+        deserialize_i32 => visit_i32,
+        deserialize_i64 => visit_i64,
+        deserialize_i128 => visit_i128,
+        deserialize_u8 => visit_u8,
+        deserialize_u16 => visit_u16,
+        deserialize_u32 => visit_u32,
+        deserialize_u64 => visit_u64,
+        deserialize_u128 => visit_u128,
+        deserialize_f32 => visit_f32,
+        deserialize_f64 => visit_f64,
+        deserialize_char => visit_char,
+    }
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_borrowed_str(self.value)
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_enum(self.value.into_deserializer())
+    }
+
+    forward_to_deserialize_any! {
+        str string bytes byte_buf unit unit_struct seq tuple
+        tuple_struct map struct identifier ignored_any
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    fn deserialize<T: DeserializeOwned>(params: &[(&str, &str)]) -> Result<T, PathError> {
+        let params = params
+            .iter()
+            .map(|&(key, value)| (Box::from(key), Box::from(value)))
+            .collect::<Vec<_>>();
+        T::deserialize(ParamsDeserializer { params: &params })
+    }
+
+    fn invalid_key(err: PathError) -> Box<str> {
+        match err {
+            PathError::InvalidValue { key, .. } => key,
+            err => panic!("expected an invalid value, found {err:?}"),
+        }
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Post {
+        user: String,
+        id: u32,
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    #[serde(rename_all = "lowercase")]
+    enum Kind {
+        Posts,
+        Comments,
+    }
+
+    #[test]
+    fn struct_target() {
+        let post = deserialize::<Post>(&[("user", "alice"), ("id", "7")]).unwrap();
+        assert_eq!(
+            post,
+            Post {
+                user: "alice".into(),
+                id: 7
+            }
+        );
+
+        let err = deserialize::<Post>(&[("user", "alice"), ("id", "seven")]).unwrap_err();
+        assert_eq!(&*invalid_key(err), "id");
+    }
+
+    #[test]
+    fn tuple_target() {
+        let (user, id) = deserialize::<(String, u32)>(&[("user", "alice"), ("id", "7")]).unwrap();
+        assert_eq!((user.as_str(), id), ("alice", 7));
+
+        let err = deserialize::<(u32, u32)>(&[("user", "alice"), ("id", "7")]).unwrap_err();
+        assert_eq!(&*invalid_key(err), "user");
+    }
+
+    #[test]
+    fn single_value_target() {
+        assert_eq!(deserialize::<u32>(&[("id", "7")]).unwrap(), 7);
+        assert_eq!(deserialize::<String>(&[("id", "7")]).unwrap(), "7");
+        assert!(deserialize::<bool>(&[("flag", "true")]).unwrap());
+
+        let err = deserialize::<i8>(&[("id", "300")]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid value for param `id`: number too large to fit in target type (found `300`)"
+        );
+    }
+
+    #[test]
+    fn option_target() {
+        assert_eq!(deserialize::<Option<u32>>(&[]).unwrap(), None);
+        assert_eq!(deserialize::<Option<u32>>(&[("id", "7")]).unwrap(), Some(7));
+    }
+
+    #[test]
+    fn enum_target() {
+        assert_eq!(
+            deserialize::<Kind>(&[("kind", "posts")]).unwrap(),
+            Kind::Posts
+        );
+        assert_eq!(
+            deserialize::<(u32, Kind)>(&[("id", "7"), ("kind", "comments")]).unwrap(),
+            (7, Kind::Comments)
+        );
+
+        let err = deserialize::<Kind>(&[("kind", "likes")]).unwrap_err();
+        assert_eq!(&*invalid_key(err), "kind");
+    }
+
+    #[test]
+    fn wrong_param_count() {
+        assert!(matches!(
+            deserialize::<u32>(&[("a", "1"), ("b", "2")]),
+            Err(PathError::WrongCount {
+                expected: 1,
+                found: 2
+            })
+        ));
+        assert!(matches!(
+            deserialize::<u32>(&[]),
+            Err(PathError::WrongCount {
+                expected: 1,
+                found: 0
+            })
+        ));
+        assert!(matches!(
+            deserialize::<(u32, u32, u32)>(&[("a", "1"), ("b", "2")]),
+            Err(PathError::WrongCount {
+                expected: 3,
+                found: 2
+            })
+        ));
+        assert!(matches!(
+            deserialize::<()>(&[("a", "1")]),
+            Err(PathError::WrongCount {
+                expected: 0,
+                found: 1
+            })
+        ));
+    }
+
+    #[test]
+    fn percent_decoded_input() {
+        let mut router = matchit::Router::new();
+        router.insert("/users/:user/posts/:id", ()).unwrap();
+        let matched = router.at("/users/J%C3%B6rg%20M/posts/%37").unwrap();
+        let params = RouteParamsExt::try_from(matched.params).unwrap();
+
+        let post = Post::deserialize(ParamsDeserializer {
+            params: &params.0[..],
+        })
+        .unwrap();
+        assert_eq!(
+            post,
+            Post {
+                user: "Jörg M".into(),
+                id: 7
+            }
+        );
+    }
+}
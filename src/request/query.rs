@@ -0,0 +1,65 @@
+//! Query string deserialization
+
+use super::{extract::ExtractFrom, Request};
+use crate::response::{Formatter, Reply, Response};
+use hyper::StatusCode;
+use serde::de::DeserializeOwned;
+use std::future::{ready, Ready};
+use thiserror::Error;
+
+/// Query string, deserialized into `T`
+///
+/// The query string is parsed as `application/x-www-form-urlencoded`. Repeated keys can be
+/// deserialized into a `Vec`. A missing query string is treated as an empty one.
+/// ```
+/// # use routerman::{method::get, request::query::Query, router::Router};
+/// # use serde::Deserialize;
+/// #[derive(Deserialize)]
+/// struct Search {
+///     q: String,
+///     #[serde(default)]
+///     tag: Vec<String>,
+/// }
+///
+/// let router: Router = Router::builder()
+///     .route(
+///         "/search",
+///         get(|Query(search): Query<Search>| async move {
+///             format!("{} ({})", search.q, search.tag.join(", "))
+///         }),
+///     )
+///     .build();
+/// ```
+#[derive(Debug, Clone)]
+pub struct Query<T>(
+    /// The deserialized query string
+    pub T,
+);
+
+impl<'a, T> ExtractFrom<&'a Request> for Query<T>
+where
+    T: DeserializeOwned,
+{
+    type Error = QueryError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn extract_from(req: &'a Request) -> Self::Future {
+        let query = req.uri().query().unwrap_or_default();
+        ready(
+            serde_html_form::from_str(query)
+                .map(Query)
+                .map_err(QueryError),
+        )
+    }
+}
+
+/// Error deserializing a query string
+#[derive(Debug, Error)]
+#[error("invalid query string: {0}")]
+pub struct QueryError(#[source] pub serde_html_form::de::Error);
+
+impl<F: Formatter> Reply<F> for QueryError {
+    fn reply(self, fmt: F) -> Response {
+        fmt.format_error(&self, StatusCode::BAD_REQUEST)
+    }
+}
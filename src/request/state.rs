@@ -0,0 +1,107 @@
+//! Shared state extraction
+
+use super::{ext::StateExt, extract::ExtractFrom, Request};
+use crate::response::{Formatter, Reply, Response};
+use std::{
+    any::{type_name, TypeId},
+    fmt,
+    future::{ready, Ready},
+};
+use thiserror::Error;
+
+/// Shared state provided through [`RouterBuilder::with_state`]
+/// ```
+/// # use routerman::{method::get, request::state::State, router::Router};
+/// # use std::sync::Arc;
+/// struct Config {
+///     name: String,
+/// }
+///
+/// let router: Router = Router::builder()
+///     .route(
+///         "/name",
+///         get(|State(config): State<Arc<Config>>| async move { config.name.clone() }),
+///     )
+///     .with_state(Arc::new(Config {
+///         name: "routerman".into(),
+///     }))
+///     .build();
+/// ```
+///
+/// [`RouterBuilder::with_state`]: crate::router::RouterBuilder::with_state
+#[derive(Debug, Clone)]
+pub struct State<S>(
+    /// A clone of the shared state
+    pub S,
+);
+
+impl<'a, S> ExtractFrom<&'a Request> for State<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    type Error = MissingState;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn extract_from(req: &'a Request) -> Self::Future {
+        ready(match req.extensions().get::<StateExt<S>>() {
+            Some(state) => Ok(State(state.0.clone())),
+            None => Err(MissingState(type_name::<S>())),
+        })
+    }
+
+    fn required_state() -> Option<StateType> {
+        Some(StateType::of::<S>())
+    }
+}
+
+/// Type of a shared state value, used to check that the state required by a route's extractors is
+/// provided when the router is built
+#[derive(Clone, Copy)]
+pub struct StateType {
+    id: TypeId,
+    name: &'static str,
+}
+
+impl StateType {
+    pub fn of<S: 'static>() -> Self {
+        Self {
+            id: TypeId::of::<S>(),
+            name: type_name::<S>(),
+        }
+    }
+
+    /// Name of the type, for diagnostics
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+}
+
+impl PartialEq for StateType {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl Eq for StateType {}
+
+impl fmt::Debug for StateType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name)
+    }
+}
+
+/// The requested state was not provided to the router
+///
+/// State required by the extractors of a route is checked when the router is built, so this only
+/// occurs for routes that bypass that check (eg. services wrapped with [`Route::from_service`]).
+///
+/// [`Route::from_service`]: crate::route::Route::from_service
+#[derive(Debug, Error)]
+#[error("missing state of type `{0}`")]
+pub struct MissingState(&'static str);
+
+impl<F: Formatter> Reply<F> for MissingState {
+    fn reply(self, fmt: F) -> Response {
+        fmt.internal_error(&self)
+    }
+}
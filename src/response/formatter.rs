@@ -0,0 +1,79 @@
+use super::{Reply, Response};
+use crate::request::{InvalidParamEncoding, Request};
+use hyper::{
+    header::{self, HeaderValue},
+    StatusCode, Uri,
+};
+use std::error::Error as StdError;
+
+/// Formats the replies generated by routerman itself, such as routing errors and the rejections of
+/// the built-in extractors.
+///
+/// Every method has a default implementation, so a custom formatter only needs to override the
+/// replies it wants to change. Errors without a dedicated method are formatted through
+/// [`Formatter::format_error`].
+/// ```
+/// # use routerman::{
+/// #   request::Request, response::{Formatter, Reply, Response}, router::RouterBuilder,
+/// # };
+/// # use hyper::StatusCode;
+/// #[derive(Clone, Default)]
+/// struct Html;
+///
+/// impl Formatter for Html {
+///     fn not_found(&self, _req: &Request) -> Response {
+///         (StatusCode::NOT_FOUND, "<h1>Not Found</h1>").reply(self.clone())
+///     }
+/// }
+///
+/// RouterBuilder::<Html>::new().build();
+/// ```
+pub trait Formatter: Clone + Send + Sync + 'static {
+    /// Formatter used for the replies to a single request. Called by the router before routing
+    /// the request, which allows replies to include information about the request they answer.
+    fn for_request(&self, req: &Request) -> Self {
+        let _ = req;
+        self.clone()
+    }
+
+    /// Reply to a request that matched no route, when there is no default route
+    fn not_found(&self, req: &Request) -> Response {
+        let _ = req;
+        (StatusCode::NOT_FOUND,).reply(self.clone())
+    }
+
+    /// Reply to a request whose method is not handled by a method router
+    fn method_not_allowed(&self, allow: &HeaderValue) -> Response {
+        (StatusCode::METHOD_NOT_ALLOWED, [(header::ALLOW, allow)]).reply(self.clone())
+    }
+
+    /// Reply to an `OPTIONS` request that a method router has no explicit route for
+    fn options(&self, allow: &HeaderValue) -> Response {
+        (StatusCode::NO_CONTENT, [(header::ALLOW, allow)]).reply(self.clone())
+    }
+
+    /// Reply redirecting a request to the correct path (eg. adding or removing a trailing slash)
+    fn redirect(&self, location: &Uri) -> Response {
+        (
+            StatusCode::PERMANENT_REDIRECT,
+            [(header::LOCATION, location.to_string())],
+        )
+            .reply(self.clone())
+    }
+
+    /// Reply to a request with route parameters that are not valid percent-encoded utf-8
+    fn bad_param(&self, err: &InvalidParamEncoding) -> Response {
+        let _ = err;
+        (StatusCode::BAD_REQUEST,).reply(self.clone())
+    }
+
+    /// Reply to an error caused by the server, rather than the request
+    fn internal_error(&self, err: &dyn StdError) -> Response {
+        self.format_error(err, StatusCode::INTERNAL_SERVER_ERROR)
+    }
+
+    /// Reply to any other error, with the given status code
+    fn format_error(&self, err: &dyn StdError, status: StatusCode) -> Response {
+        (status, err.to_string()).reply(self.clone())
+    }
+}
@@ -1,8 +1,8 @@
-use super::{DefaultFormatter, Reply, ReplyPart, Response};
+use super::{Formatter, Reply, ReplyPart, Response};
 use crate::{
-    method::MethodNotAllowed,
+    method::{MethodNotAllowed, MethodOptions},
     mime::TEXT_PLAIN,
-    router::{RouteError, RouteErrorKind},
+    router::{RouteError, RouteErrorKind, UrlForError},
 };
 use hyper::{
     body::Bytes,
@@ -14,12 +14,12 @@ use hyper::{
     },
     Body, StatusCode, Uri,
 };
-use std::{borrow::Cow, fmt::Display};
+use std::{borrow::Cow, error::Error as StdError, fmt::Display, io};
 
 // Convinence macro for repeating reply implementations
 macro_rules! impl_reply {
-    ($(impl$(<$($gen_arg:ident),*>)? Reply<$($gen_ty:ty),*> for $items:tt),*) => {
-        $(impl_reply!(@impl ($($($gen_arg),*)?) ($($gen_ty),*) $items);)*
+    ($(impl$(<$($gen_arg:ident $(: $gen_bound:path)?),*>)? Reply<$($gen_ty:ty),*> for $items:tt),*) => {
+        $(impl_reply!(@impl ($($($gen_arg $(: $gen_bound)?),*)?) ($($gen_ty),*) $items);)*
     };
 
     (@impl $gen_arg:tt $gen_ty:tt { $($ty:tt => $spec:tt),* $(,)? }) => {
@@ -30,10 +30,10 @@ macro_rules! impl_reply {
     };
 
     (@impl
-        ($($gen_arg:ident),*) ($fmt_ty:ty)
+        ($($gen:tt)*) ($fmt_ty:ty)
         $ty:ty => [ from $from:ty ]
     ) => {
-        impl<$($gen_arg),*> Reply<$fmt_ty> for $ty {
+        impl<$($gen)*> Reply<$fmt_ty> for $ty {
             fn reply(self, fmt: $fmt_ty) -> Response {
                 <$from>::from(self).reply(fmt)
             }
@@ -41,10 +41,10 @@ macro_rules! impl_reply {
     };
 
     (@impl
-        ($($gen_arg:ident),*) ($fmt_ty:ty)
+        ($($gen:tt)*) ($fmt_ty:ty)
         $ty:ty => [ from_parts ]
     ) => {
-        impl<$($gen_arg),*> Reply<$fmt_ty> for $ty
+        impl<$($gen)*> Reply<$fmt_ty> for $ty
         where
             Self: ReplyPart<$fmt_ty>,
         {
@@ -55,14 +55,58 @@ macro_rules! impl_reply {
     };
 }
 
-impl Reply<DefaultFormatter> for hyper::http::Error {
-    fn reply(self, fmt: DefaultFormatter) -> Response {
-        (StatusCode::INTERNAL_SERVER_ERROR, self.to_string()).reply(fmt)
+impl<F: Formatter> Reply<F> for hyper::http::Error {
+    fn reply(self, fmt: F) -> Response {
+        fmt.internal_error(&self)
+    }
+}
+
+impl<F: Formatter> Reply<F> for hyper::Error {
+    fn reply(self, fmt: F) -> Response {
+        fmt.format_error(&self, StatusCode::BAD_REQUEST)
+    }
+}
+
+/// Errors of tower services are usually boxed (eg. the errors of layers). Timeouts are replied to
+/// with `504 Gateway Timeout`, any other error is an internal error.
+impl<F: Formatter> Reply<F> for Box<dyn StdError + Send + Sync> {
+    fn reply(self, fmt: F) -> Response {
+        match is_timeout(&*self) {
+            true => fmt.format_error(&*self, StatusCode::GATEWAY_TIMEOUT),
+            false => fmt.internal_error(&*self),
+        }
+    }
+}
+
+/// Messages of the timeout errors of `tower::timeout` and `tokio::time`, which are recognized
+/// without depending on either crate
+const TIMEOUT_MESSAGES: [&str; 2] = ["request timed out", "deadline has elapsed"];
+
+/// Whether an error, or any of its sources, is a timeout
+fn is_timeout(err: &(dyn StdError + 'static)) -> bool {
+    let mut source = Some(err);
+    while let Some(err) = source {
+        if let Some(err) = err.downcast_ref::<io::Error>() {
+            if err.kind() == io::ErrorKind::TimedOut {
+                return true;
+            }
+        }
+        if TIMEOUT_MESSAGES.contains(&&*err.to_string()) {
+            return true;
+        }
+        source = err.source();
+    }
+    false
+}
+
+impl<F: Formatter> Reply<F> for UrlForError {
+    fn reply(self, fmt: F) -> Response {
+        fmt.internal_error(&self)
     }
 }
 
 impl_reply!(
-    impl Reply<DefaultFormatter> for {
+    impl<F: Formatter> Reply<F> for {
         (
             InvalidHeaderName, InvalidHeaderValue,
             InvalidMethod, InvalidStatusCode,
@@ -71,8 +115,8 @@ impl_reply!(
     }
 );
 
-impl Reply<DefaultFormatter> for RouteError {
-    fn reply(self, fmt: DefaultFormatter) -> Response {
+impl<F: Formatter> Reply<F> for RouteError {
+    fn reply(self, fmt: F) -> Response {
         // Replace the path portion of a uri
         fn replace_path(uri: &Uri, path: impl Display) -> Uri {
             let mut parts = uri.to_owned().into_parts();
@@ -89,36 +133,29 @@ impl Reply<DefaultFormatter> for RouteError {
 
         let Self { request: req, kind } = self;
         match kind {
-            RouteErrorKind::NotFound => (StatusCode::NOT_FOUND,).reply(fmt),
-            RouteErrorKind::ExtraTrailingSlash => (
-                StatusCode::PERMANENT_REDIRECT,
-                [(
-                    header::LOCATION,
-                    replace_path(req.uri(), req.uri().path().strip_suffix('/').unwrap())
-                        .to_string(),
-                )],
-            )
-                .reply(fmt),
-            RouteErrorKind::MissingTrailingSlash => (
-                StatusCode::PERMANENT_REDIRECT,
-                [(
-                    header::LOCATION,
-                    replace_path(req.uri(), format_args!("{}/", req.uri().path())).to_string(),
-                )],
-            )
-                .reply(fmt),
-            RouteErrorKind::Param(_) => (StatusCode::BAD_REQUEST,).reply(fmt),
+            RouteErrorKind::NotFound => fmt.not_found(&req),
+            RouteErrorKind::ExtraTrailingSlash => fmt.redirect(&replace_path(
+                req.uri(),
+                req.uri().path().strip_suffix('/').unwrap(),
+            )),
+            RouteErrorKind::MissingTrailingSlash => fmt.redirect(&replace_path(
+                req.uri(),
+                format_args!("{}/", req.uri().path()),
+            )),
+            RouteErrorKind::Param(err) => fmt.bad_param(&err),
         }
     }
 }
 
-impl Reply<DefaultFormatter> for MethodNotAllowed<'_> {
-    fn reply(self, fmt: DefaultFormatter) -> Response {
-        (
-            StatusCode::METHOD_NOT_ALLOWED,
-            [(header::ALLOW, self.allow_header)],
-        )
-            .reply(fmt)
+impl<F: Formatter> Reply<F> for MethodNotAllowed<'_> {
+    fn reply(self, fmt: F) -> Response {
+        fmt.method_not_allowed(self.allow_header)
+    }
+}
+
+impl<F: Formatter> Reply<F> for MethodOptions<'_> {
+    fn reply(self, fmt: F) -> Response {
+        fmt.options(self.allow_header)
     }
 }
 
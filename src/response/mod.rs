@@ -2,9 +2,15 @@ use std::convert::Infallible;
 
 use hyper::Body;
 
+mod formatter;
 mod impls;
 mod parts;
 
+pub use formatter::Formatter;
+
+#[cfg(feature = "macros")]
+pub use routerman_macros::Reply;
+
 pub type Response = hyper::Response<Body>;
 
 pub trait Reply<Fmt> {
@@ -18,6 +24,8 @@ pub trait ReplyPart<Fmt> {
 #[derive(Debug, Clone, Copy, Default)]
 pub struct DefaultFormatter;
 
+impl Formatter for DefaultFormatter {}
+
 impl<T, E, Fmt> Reply<Fmt> for std::result::Result<T, E>
 where
     T: Reply<Fmt>,
@@ -1,15 +1,53 @@
+#[cfg(feature = "openapi")]
+use crate::openapi::Operation;
 use crate::{
-    request::Request,
-    response::{Reply, Response},
+    request::{
+        extract::{FromRequest, FromRequestParts, ViaParts},
+        state::StateType,
+        Request,
+    },
+    response::{Formatter, Reply, Response},
 };
-use futures_util::{Future, FutureExt};
-use std::{pin::Pin, sync::Arc};
+use futures_util::{future::poll_fn, Future, FutureExt};
+use hyper::Method;
+use std::{
+    convert::Infallible,
+    marker::PhantomData,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+};
+use tower_layer::Layer;
+use tower_service::Service;
 
 pub(crate) type BoxFuture<Out> = Pin<Box<dyn Future<Output = Out> + Send + 'static>>;
 type HandlerFn<Fmt> = dyn Fn(Request, Fmt) -> BoxFuture<Response> + Send + Sync + 'static;
+type MakeHandlerFn<Fmt> = dyn Fn(&Fmt) -> Arc<HandlerFn<Fmt>> + Send + Sync + 'static;
 
-#[derive(Clone)]
-pub struct Route<Fmt>(Arc<HandlerFn<Fmt>>);
+pub struct Route<Fmt> {
+    handler: Handler<Fmt>,
+
+    /// Methods accepted by the route, or `None` if it accepts any method
+    methods: Option<Arc<[Method]>>,
+
+    /// State required by the route's extractors that has not been provided to it yet
+    required_state: Arc<[StateType]>,
+
+    #[cfg(feature = "openapi")]
+    operations: Arc<[(Method, Operation)]>,
+}
+
+impl<Fmt> Clone for Route<Fmt> {
+    fn clone(&self) -> Self {
+        Route {
+            handler: self.handler.clone(),
+            methods: self.methods.clone(),
+            required_state: self.required_state.clone(),
+            #[cfg(feature = "openapi")]
+            operations: self.operations.clone(),
+        }
+    }
+}
 
 impl<Fmt> Route<Fmt> {
     pub fn new<H, Args>(handler: H) -> Self
@@ -20,8 +58,234 @@ impl<Fmt> Route<Fmt> {
         handler.into_route()
     }
 
+    /// Create a route from a tower [`Service`]. The service is cloned for every request, and driven
+    /// to readiness before being called. Errors returned by the service are replied to using the
+    /// route's formatter.
+    pub fn from_service<S>(service: S) -> Self
+    where
+        S: Service<Request, Response = Response> + Clone + Send + 'static,
+        S::Future: Send + 'static,
+        S::Error: Reply<Fmt>,
+        Fmt: Send + Sync + 'static,
+    {
+        let service = Mutex::new(service);
+        Route::from_fn(move |req: Request, fmt: Fmt| {
+            let mut service = service.lock().unwrap().clone();
+            Box::pin(async move {
+                if let Err(err) = poll_fn(|cx| service.poll_ready(cx)).await {
+                    return err.reply(fmt);
+                }
+
+                match service.call(req).await {
+                    Ok(res) => res,
+                    Err(err) => err.reply(fmt),
+                }
+            })
+        })
+    }
+
+    /// Wrap the route with a tower [`Layer`]. The layer's service is created when the route is built
+    /// into a router, and cloned for every request. Errors returned by the service are replied to
+    /// using the route's formatter.
+    pub fn layer<L>(self, layer: L) -> Self
+    where
+        L: Layer<RouteService<Fmt>> + Send + Sync + 'static,
+        L::Service: Service<Request, Response = Response> + Clone + Send + 'static,
+        <L::Service as Service<Request>>::Future: Send + 'static,
+        <L::Service as Service<Request>>::Error: Reply<Fmt>,
+        Fmt: Formatter,
+    {
+        let methods = self.methods.clone();
+        let required_state = self.required_state.clone();
+        #[cfg(feature = "openapi")]
+        let operations = self.operations.clone();
+
+        // The inner service needs the router's formatter, which is only known once the route is
+        // built into a router
+        Route {
+            required_state,
+            #[cfg(feature = "openapi")]
+            operations,
+            ..Route::from_make_fn(move |fmt: &Fmt| {
+                Route::from_service(layer.layer(RouteService {
+                    handler: self.make_handler(fmt),
+                    formatter: fmt.clone(),
+                }))
+            })
+            .with_methods(methods)
+        }
+    }
+
+    /// Describe how the route handles a method in the generated OpenAPI document. Describing the
+    /// same method twice replaces the first description.
+    ///
+    /// Method routers should be described through [`MethodRouter::operation`] instead.
+    ///
+    /// [`MethodRouter::operation`]: crate::method::MethodRouter::operation
+    #[cfg(feature = "openapi")]
+    pub fn operation(mut self, method: Method, operation: Operation) -> Self {
+        let mut operations = self
+            .operations
+            .iter()
+            .filter(|(m, _)| *m != method)
+            .cloned()
+            .collect::<Vec<_>>();
+        operations.push((method, operation));
+        self.operations = operations.into();
+        self
+    }
+
+    fn from_fn<F>(handler: F) -> Self
+    where
+        F: Fn(Request, Fmt) -> BoxFuture<Response> + Send + Sync + 'static,
+    {
+        Route {
+            handler: Handler::Ready(Arc::new(handler)),
+            methods: None,
+            required_state: Arc::new([]),
+            #[cfg(feature = "openapi")]
+            operations: Arc::new([]),
+        }
+    }
+
+    /// Create a route that is only known once it is built into a router, from the router's
+    /// formatter
+    pub(crate) fn from_make_fn<F>(make: F) -> Self
+    where
+        F: Fn(&Fmt) -> Route<Fmt> + Send + Sync + 'static,
+    {
+        Route {
+            handler: Handler::Unbound(Arc::new(move |fmt| make(fmt).make_handler(fmt))),
+            methods: None,
+            required_state: Arc::new([]),
+            #[cfg(feature = "openapi")]
+            operations: Arc::new([]),
+        }
+    }
+
+    /// Modify every request before the route handles it
+    pub(crate) fn map_request<F>(self, map: F) -> Self
+    where
+        F: Fn(&mut Request) + Send + Sync + 'static,
+        Fmt: 'static,
+    {
+        let map = Arc::new(map);
+        let map_handler = move |handler: Arc<HandlerFn<Fmt>>| -> Arc<HandlerFn<Fmt>> {
+            let map = map.clone();
+            Arc::new(move |mut req, fmt| {
+                map(&mut req);
+                handler(req, fmt)
+            })
+        };
+        let handler = match self.handler {
+            Handler::Ready(handler) => Handler::Ready(map_handler(handler)),
+            Handler::Unbound(make) => Handler::Unbound(Arc::new(move |fmt| map_handler(make(fmt)))),
+        };
+        Route { handler, ..self }
+    }
+
+    /// Prepare the route to handle the requests of a router built with a formatter
+    pub(crate) fn bind(self, fmt: &Fmt) -> Self {
+        Route {
+            handler: Handler::Ready(self.make_handler(fmt)),
+            ..self
+        }
+    }
+
+    /// Handler of the route, for a router built with a formatter
+    pub(crate) fn make_handler(&self, fmt: &Fmt) -> Arc<HandlerFn<Fmt>> {
+        match &self.handler {
+            Handler::Ready(handler) => handler.clone(),
+            Handler::Unbound(make) => make(fmt),
+        }
+    }
+
+    /// Handler of a route that was bound to its router. See [`Route::bind`].
     pub(crate) fn handler_fn(&self) -> &HandlerFn<Fmt> {
-        &*self.0
+        match &self.handler {
+            Handler::Ready(handler) => &**handler,
+            Handler::Unbound(_) => unreachable!("routes are bound when the router is built"),
+        }
+    }
+
+    /// Methods accepted by the route, or `None` if it accepts any method
+    pub(crate) fn methods(&self) -> Option<&Arc<[Method]>> {
+        self.methods.as_ref()
+    }
+
+    pub(crate) fn with_methods(mut self, methods: Option<Arc<[Method]>>) -> Self {
+        self.methods = methods;
+        self
+    }
+
+    /// State required by the route that has not been provided to it yet
+    pub(crate) fn required_state(&self) -> &[StateType] {
+        &self.required_state
+    }
+
+    pub(crate) fn with_required_state(
+        mut self,
+        state: impl IntoIterator<Item = StateType>,
+    ) -> Self {
+        let mut required = Vec::<StateType>::new();
+        for ty in state {
+            if !required.contains(&ty) {
+                required.push(ty);
+            }
+        }
+        self.required_state = required.into();
+        self
+    }
+
+    #[cfg(feature = "openapi")]
+    pub(crate) fn operations(&self) -> &Arc<[(Method, Operation)]> {
+        &self.operations
+    }
+}
+
+/// Handler of a route, which may depend on the formatter of the router it is built into
+enum Handler<Fmt> {
+    Ready(Arc<HandlerFn<Fmt>>),
+
+    /// Created when the route is built into a router (eg. for layered routes)
+    Unbound(Arc<MakeHandlerFn<Fmt>>),
+}
+
+impl<Fmt> Clone for Handler<Fmt> {
+    fn clone(&self) -> Self {
+        match self {
+            Handler::Ready(handler) => Handler::Ready(handler.clone()),
+            Handler::Unbound(make) => Handler::Unbound(make.clone()),
+        }
+    }
+}
+
+/// Service that calls a route. This is the innermost service of a layered route.
+///
+/// It holds the formatter the router was built with, which is specialized for every request it
+/// receives through [`Formatter::for_request`]. Layers are therefore free to replace the request
+/// entirely.
+#[derive(Clone)]
+pub struct RouteService<Fmt> {
+    handler: Arc<HandlerFn<Fmt>>,
+    formatter: Fmt,
+}
+
+impl<Fmt> Service<Request> for RouteService<Fmt>
+where
+    Fmt: Formatter,
+{
+    type Response = Response;
+    type Error = Infallible;
+    type Future = BoxFuture<Result<Response, Infallible>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, req: Request) -> Self::Future {
+        let fmt = self.formatter.for_request(&req);
+        Box::pin((self.handler)(req, fmt).map(Ok))
     }
 }
 
@@ -33,16 +297,10 @@ pub trait RouteHandler<Fmt, Args> {
     fn into_route(self) -> Route<Fmt>;
 }
 
-/// impl Handler for `async Fn(Req) -> Out`
-impl<H, Fut, Fmt, Out> RouteHandler<Fmt, (Request,)> for H
-where
-    H: Fn(Request) -> Fut + Send + Sync + 'static,
-    Fut: Future<Output = Out> + Send + 'static,
-    Out: Reply<Fmt>,
-    Fmt: Send + Sync + 'static,
-{
+impl<Fmt> RouteHandler<Fmt, ()> for Route<Fmt> {
+    #[inline]
     fn into_route(self) -> Route<Fmt> {
-        (move |req, fmt| self(req).map(|res| res.reply(fmt))).into_route()
+        self
     }
 }
 
@@ -54,6 +312,88 @@ where
     Fmt: Send + Sync + 'static,
 {
     fn into_route(self) -> Route<Fmt> {
-        Route(Arc::new(move |req, fmt| Box::pin(self(req, fmt))))
+        Route::from_fn(move |req, fmt| Box::pin(self(req, fmt)))
     }
 }
+
+/// Marker for handlers taking extractors as arguments. The M type argument is the marker used by
+/// the last extractor's [`FromRequest`] implementation.
+#[doc(hidden)]
+pub struct ViaExtractors<M>(PhantomData<M>);
+
+/// impl Handler for `async Fn(T1, T2, ..., Tn) -> Out`, where every argument is extracted from the
+/// request. Only the last argument may consume the request.
+macro_rules! impl_route_handler {
+    () => {
+        impl<H, Fut, Fmt, Out> RouteHandler<Fmt, (ViaExtractors<ViaParts>,)> for H
+        where
+            H: Fn() -> Fut + Send + Sync + 'static,
+            Fut: Future<Output = Out> + Send + 'static,
+            Out: Reply<Fmt>,
+            Fmt: Send + Sync + 'static,
+        {
+            fn into_route(self) -> Route<Fmt> {
+                let handler = Arc::new(self);
+                (move |_req: Request, fmt: Fmt| {
+                    let handler = handler.clone();
+                    async move { handler().await.reply(fmt) }
+                })
+                .into_route()
+            }
+        }
+    };
+    ($($ty:ident),*) => {
+        impl_route_handler!(@split [] $($ty),*);
+    };
+
+    (@split [$($parts:ident)*] $head:ident, $($rest:ident),+) => {
+        impl_route_handler!(@split [$($parts)* $head] $($rest),+);
+    };
+    (@split [$($parts:ident)*] $last:ident) => {
+        impl<H, Fut, Fmt, Out, M, $($parts,)* $last>
+            RouteHandler<Fmt, (ViaExtractors<M>, $($parts,)* $last)> for H
+        where
+            H: Fn($($parts,)* $last) -> Fut + Send + Sync + 'static,
+            Fut: Future<Output = Out> + Send + 'static,
+            Out: Reply<Fmt>,
+            Fmt: Send + Sync + 'static,
+            $($parts: FromRequestParts<Fmt> + Send,)*
+            $last: FromRequest<Fmt, M> + Send,
+        {
+            fn into_route(self) -> Route<Fmt> {
+                let handler = Arc::new(self);
+                (move |req: Request, fmt: Fmt| {
+                    let handler = handler.clone();
+                    async move {
+                        $(
+                            #[allow(non_snake_case)]
+                            let $parts = match $parts::from_request_parts(&req).await {
+                                Ok(value) => value,
+                                Err(rejection) => return rejection.reply(fmt),
+                            };
+                        )*
+
+                        #[allow(non_snake_case)]
+                        let $last = match $last::from_request(req).await {
+                            Ok(value) => value,
+                            Err(rejection) => return rejection.reply(fmt),
+                        };
+
+                        handler($($parts,)* $last).await.reply(fmt)
+                    }
+                })
+                .into_route()
+                .with_required_state(
+                    [
+                        $(<$parts as FromRequestParts<Fmt>>::required_state(),)*
+                        <$last as FromRequest<Fmt, M>>::required_state(),
+                    ]
+                    .into_iter()
+                    .flatten(),
+                )
+            }
+        }
+    };
+}
+
+all_the_tuples!(impl_route_handler);
@@ -1,51 +1,379 @@
 use core::fmt;
 use std::{
+    collections::HashMap,
     convert::Infallible,
     future::{Future, Ready},
-    net::SocketAddr,
     pin::Pin,
     sync::Arc,
     task::{Context, Poll},
 };
 
 use futures_util::ready;
-use hyper::{server::conn::AddrStream, Body};
-use matchit::MatchError;
+use hyper::{http::Extensions, Body, Method, Uri};
+use matchit::{InsertError, MatchError};
+use percent_encoding::{utf8_percent_encode, AsciiSet, CONTROLS};
 use pin_project::pin_project;
 use thiserror::Error;
+use tower_layer::Layer;
 use tower_service::Service;
 
+#[cfg(feature = "tls")]
+use crate::tls::TlsInfo;
 use crate::{
+    connection::{Connected, ConnectionInfo},
+    pattern::{self, Token},
     request::{
-        ext::{InvalidParamEncoding, RemoteAddrExt, RouteParamsExt},
+        ext::{
+            InvalidParamEncoding, MatchedPath, NestPrefixExt, RouteNamesExt, RouteParamsExt,
+            StateExt,
+        },
+        forwarded::ForwardedExt,
+        state::StateType,
         Request,
     },
-    response::{DefaultFormatter, Reply, Response},
-    route::{BoxFuture, Route, RouteHandler},
+    response::{DefaultFormatter, Formatter, Reply, Response},
+    route::{BoxFuture, Route, RouteHandler, RouteService},
 };
+#[cfg(feature = "openapi")]
+use crate::{
+    mime,
+    openapi::{OpenApi, Operation},
+};
+
+pub use crate::request::forwarded::{InvalidIpNetwork, IpNetwork, ProxyHeader};
 
 pub struct Router<Fmt = DefaultFormatter> {
     formatter: Fmt,
     inner: Arc<RouterImpl<Fmt>>,
 }
 
+impl<Fmt: Clone> Clone for Router<Fmt> {
+    fn clone(&self) -> Self {
+        Self {
+            formatter: self.formatter.clone(),
+            inner: self.inner.clone(),
+        }
+    }
+}
+
 struct RouterImpl<Fmt> {
-    inner: matchit::Router<Route<Fmt>>,
+    inner: matchit::Router<Endpoint<Fmt>>,
     default: Option<Route<Fmt>>,
+
+    /// Default routes of nested routers, sorted from the longest prefix to the shortest
+    nested_defaults: Vec<(Arc<str>, Route<Fmt>)>,
+
+    state: Vec<Arc<StateFn>>,
+    names: Arc<RouteNames>,
+    trusted_proxies: Vec<(IpNetwork, ProxyHeader)>,
+
+    /// Descriptions of all the routes, in the order they were registered
+    routes: Vec<RouteInfo>,
+}
+
+/// Inserts a clone of some shared state into a request's extensions
+type StateFn = dyn Fn(&mut Extensions) + Send + Sync + 'static;
+
+/// Shared state provided to a router builder
+#[derive(Clone)]
+struct StateEntry {
+    ty: StateType,
+    insert: Arc<StateFn>,
+}
+
+impl<Fmt> RouterImpl<Fmt> {
+    /// Find the default route responsible for a path, along with the prefix of the nested router it
+    /// belongs to (if any)
+    fn default_for(&self, path: &str) -> Option<(&Route<Fmt>, Option<&Arc<str>>)> {
+        self.nested_default_for(path)
+            .map(|(route, prefix)| (route, Some(prefix)))
+            .or_else(|| self.default.as_ref().map(|route| (route, None)))
+    }
+
+    /// Find the default route of the innermost nested router whose prefix contains a path
+    fn nested_default_for(&self, path: &str) -> Option<(&Route<Fmt>, &Arc<str>)> {
+        self.nested_defaults
+            .iter()
+            .find(|(prefix, _)| match path.strip_prefix(&**prefix) {
+                Some(rest) => rest.is_empty() || rest.starts_with('/'),
+                None => false,
+            })
+            .map(|(prefix, route)| (route, prefix))
+    }
+
+    /// Route a request to its handler
+    fn dispatch(&self, mut req: Request, fmt: Fmt) -> RequestFuture
+    where
+        Fmt: Formatter,
+    {
+        let fmt = fmt.for_request(&req);
+
+        // Add a clone of the shared state to the request's extensions
+        for state in &self.state {
+            state(req.extensions_mut());
+        }
+        req.extensions_mut()
+            .insert(RouteNamesExt(self.names.clone()));
+        if !self.trusted_proxies.is_empty() {
+            if let Some(forwarded) = ForwardedExt::resolve(&req, &self.trusted_proxies) {
+                req.extensions_mut().insert(forwarded);
+            }
+        }
+
+        let res = match self.inner.at(req.uri().path()) {
+            // A route was found. Attempt to parse the parameters and run the handler. If the
+            // parameters are invalid (eg. invalid percent-encoded utf8), reply with error.
+            Ok(route) => match RouteParamsExt::try_from(route.params) {
+                Ok(params) => Ok((
+                    &route.value.route,
+                    route.value.prefix.as_ref(),
+                    Some(params),
+                    MatchedPath::Route(route.value.path.clone()),
+                )),
+                Err(err) => Err(RouteErrorKind::Param(err)),
+            },
+            // No route was found. Use the fallback if it exists, otherwise reply with error.
+            Err(MatchError::NotFound) => match self.default_for(req.uri().path()) {
+                Some((route, prefix)) => {
+                    Ok((route, prefix, None, MatchedPath::Default(prefix.cloned())))
+                }
+                None => Err(RouteErrorKind::NotFound),
+            },
+
+            // There was either a trailing slash when there shouldn't be, or there wasn't a trailing
+            // slash when there should be. The path is still unmatched, so the default route of a
+            // nested router containing it takes precedence. Otherwise, reply with an error that
+            // indicates to redirect the user to the correct path.
+            Err(err @ (MatchError::ExtraTrailingSlash | MatchError::MissingTrailingSlash)) => {
+                match self.nested_default_for(req.uri().path()) {
+                    Some((route, prefix)) => Ok((
+                        route,
+                        Some(prefix),
+                        None,
+                        MatchedPath::Default(Some(prefix.clone())),
+                    )),
+                    None => Err(match err {
+                        MatchError::ExtraTrailingSlash => RouteErrorKind::ExtraTrailingSlash,
+                        _ => RouteErrorKind::MissingTrailingSlash,
+                    }),
+                }
+            }
+        };
+
+        // Finally return the request future, either containing the route's future or an immediate
+        // reponse
+        match res {
+            Ok((route, prefix, params, matched_path)) => {
+                req.extensions_mut().insert(matched_path);
+                if let Some(params) = params {
+                    req.extensions_mut().insert(params);
+                }
+                if let Some(prefix) = prefix {
+                    req.extensions_mut().insert(NestPrefixExt(prefix.clone()));
+                }
+
+                RequestFuture::Route((route.handler_fn())(req, fmt))
+            }
+            Err(err) => RequestFuture::Response(Some(
+                RouteError {
+                    request: req,
+                    kind: err,
+                }
+                .reply(fmt),
+            )),
+        }
+    }
+}
+
+/// Description of a route registered in a router
+#[derive(Debug, Clone)]
+pub struct RouteInfo {
+    path: Arc<str>,
+    name: Option<String>,
+    methods: Option<Arc<[Method]>>,
+
+    #[cfg(feature = "openapi")]
+    operations: Arc<[(Method, Operation)]>,
+}
+
+impl RouteInfo {
+    /// Path pattern of the route (eg. `/users/:id`)
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Name of the route, if it was registered with [`RouterBuilder::route_named`]
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Methods with a handler, if the route is a [`MethodRouter`] without a fallback route. `None`
+    /// if the route accepts any method.
+    ///
+    /// Methods that are handled implicitly (`HEAD` and `OPTIONS`) are not listed.
+    ///
+    /// [`MethodRouter`]: crate::method::MethodRouter
+    pub fn methods(&self) -> Option<&[Method]> {
+        self.methods.as_deref()
+    }
+
+    /// OpenAPI descriptions of the methods of the route
+    #[cfg(feature = "openapi")]
+    pub fn operations(&self) -> &[(Method, Operation)] {
+        &self.operations
+    }
+}
+
+/// A route registered to a path
+struct Endpoint<Fmt> {
+    path: Arc<str>,
+    route: Route<Fmt>,
+    name: Option<String>,
+
+    /// Prefix of the nested router the route was registered in
+    prefix: Option<Arc<str>>,
+}
+
+impl<Fmt> Endpoint<Fmt> {
+    fn info(&self) -> RouteInfo {
+        RouteInfo {
+            path: self.path.clone(),
+            name: self.name.clone(),
+            methods: self.route.methods().cloned(),
+            #[cfg(feature = "openapi")]
+            operations: self.route.operations().clone(),
+        }
+    }
+}
+
+#[cfg(feature = "openapi")]
+impl<Fmt> Endpoint<Fmt>
+where
+    Fmt: Send + Sync + 'static,
+{
+    /// Route serving a pre-generated OpenAPI document
+    fn openapi(path: String, document: serde_json::Value) -> Self {
+        use hyper::{body::Bytes, header};
+
+        let document = Bytes::from(document.to_string());
+        let route = Route::new(move |_req: Request, _fmt: Fmt| {
+            let mut res = Response::new(Body::from(document.clone()));
+            res.headers_mut()
+                .insert(header::CONTENT_TYPE, mime::APPLICATION_JSON.header());
+            std::future::ready(res)
+        });
+
+        Endpoint {
+            path: Arc::from(path),
+            route,
+            name: None,
+            prefix: None,
+        }
+    }
 }
 
 impl<Fmt> Router<Fmt> {
+    /// Start building a router. The [`Formatter`] is inferred from the router's type, which
+    /// defaults to [`DefaultFormatter`] when annotated as a plain `Router`:
+    /// ```
+    /// # use routerman::{method::get, problem::ProblemJsonFormatter, router::Router};
+    /// let router: Router = Router::builder()
+    ///     .route("/", get(|| async { "Hello, World!" }))
+    ///     .build();
+    ///
+    /// let problems = Router::<ProblemJsonFormatter>::builder()
+    ///     .route("/", get(|| async { "Hello, World!" }))
+    ///     .build();
+    /// ```
+    ///
+    /// [`Formatter`]: crate::response::Formatter
     pub fn builder() -> RouterBuilder<Fmt> {
+        RouterBuilder::new()
+    }
+
+    /// Generate the path of a named route, filling in its parameters. See
+    /// [`RouterBuilder::route_named`].
+    pub fn url_for(&self, name: &str, params: &[(&str, &str)]) -> Result<String, UrlForError> {
+        self.inner.names.url_for(name, params)
+    }
+
+    /// Describe every route of the router, in the order the routes were registered. Routes of nested
+    /// routers are listed with their full path.
+    /// ```
+    /// # use routerman::{method::{get, post}, request::Request, router::Router};
+    /// let router: Router = Router::builder()
+    ///     .route_named(
+    ///         "users",
+    ///         "/users",
+    ///         get(|_req: Request| async { "List" }) | post(|_req: Request| async { "Create" }),
+    ///     )
+    ///     .route("/health", |_req: Request| async { "Ok" })
+    ///     .build();
+    ///
+    /// for route in router.routes() {
+    ///     println!("{:?} {} {:?}", route.methods(), route.path(), route.name());
+    /// }
+    /// ```
+    pub fn routes(&self) -> impl Iterator<Item = &RouteInfo> {
+        self.inner.routes.iter()
+    }
+
+    /// Whether the router has a default route for paths outside of any nested router
+    pub fn has_default_route(&self) -> bool {
+        self.inner.default.is_some()
+    }
+
+    /// Prefixes of the nested routers that have their own default route, from the longest to the
+    /// shortest
+    pub fn nested_default_routes(&self) -> impl Iterator<Item = &str> {
+        self.inner
+            .nested_defaults
+            .iter()
+            .map(|(prefix, _)| &**prefix)
+    }
+
+    /// Generate an OpenAPI document describing the router. See [`OpenApi::document`].
+    #[cfg(feature = "openapi")]
+    pub fn openapi(&self, api: &OpenApi) -> serde_json::Value {
+        api.document(self.routes())
+    }
+}
+
+pub struct RouterBuilder<Fmt = DefaultFormatter> {
+    routes: Vec<Endpoint<Fmt>>,
+    default: Option<Route<Fmt>>,
+    nested_defaults: Vec<(String, Route<Fmt>)>,
+    state: Vec<StateEntry>,
+    trusted_proxies: Vec<(IpNetwork, ProxyHeader)>,
+
+    /// Path to serve the router's OpenAPI document at
+    #[cfg(feature = "openapi")]
+    openapi: Option<(String, OpenApi)>,
+
+    /// Errors that can be detected before building the router (eg. merge conflicts). They are
+    /// reported along with any routing errors when the router is built.
+    errors: Vec<BuildErrorKind>,
+}
+
+impl<Fmt> RouterBuilder<Fmt> {
+    pub fn new() -> Self {
         RouterBuilder {
             routes: Vec::new(),
             default: None,
+            nested_defaults: Vec::new(),
+            state: Vec::new(),
+            trusted_proxies: Vec::new(),
+            #[cfg(feature = "openapi")]
+            openapi: None,
+            errors: Vec::new(),
         }
     }
 }
 
-pub struct RouterBuilder<Fmt = DefaultFormatter> {
-    routes: Vec<(String, Route<Fmt>)>,
-    default: Option<Route<Fmt>>,
+impl<Fmt> Default for RouterBuilder<Fmt> {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl<Fmt> RouterBuilder<Fmt>
@@ -57,7 +385,42 @@ where
         P: Into<String>,
         H: RouteHandler<Fmt, Args>,
     {
-        self.routes.push((path.into(), handler.into_route()));
+        self.routes.push(Endpoint {
+            path: Arc::from(path.into()),
+            route: handler.into_route(),
+            name: None,
+            prefix: None,
+        });
+        self
+    }
+
+    /// Add a route that can be referred to by name when generating urls, instead of hard-coding its
+    /// path. Names are global to the router: They are kept as-is when merging or nesting routers and
+    /// must be unique.
+    /// ```
+    /// # use routerman::{method::get, request::{Request, RequestExt}, router::Router};
+    /// let router: Router = Router::builder()
+    ///     .route_named("user.show", "/users/:id", get(|_req: Request| async { "User" }))
+    ///     .route(
+    ///         "/me",
+    ///         get(|req: Request| async move { req.url_for("user.show", &[("id", "me")]).unwrap() }),
+    ///     )
+    ///     .build();
+    ///
+    /// assert_eq!(router.url_for("user.show", &[("id", "a b")]).unwrap(), "/users/a%20b");
+    /// ```
+    pub fn route_named<N, P, H, Args>(mut self, name: N, path: P, handler: H) -> Self
+    where
+        N: Into<String>,
+        P: Into<String>,
+        H: RouteHandler<Fmt, Args>,
+    {
+        self.routes.push(Endpoint {
+            path: Arc::from(path.into()),
+            route: handler.into_route(),
+            name: Some(name.into()),
+            prefix: None,
+        });
         self
     }
 
@@ -69,49 +432,597 @@ where
         self
     }
 
-    pub fn merge(self, router: RouterBuilder<Fmt>) -> Self {
-        let Self {
-            mut routes,
-            mut default,
-        } = self;
+    /// Make a value available to all the handlers of the router. The value is cloned for every
+    /// request and can be accessed through the [`State`] extractor or [`RequestExt::state`].
+    ///
+    /// Values are identified by their type, so only one value of each type can be provided to a
+    /// builder. The state of a merged or nested router is only available to its own routes, where
+    /// it takes precedence over state of the same type provided to the outer router.
+    ///
+    /// Building a router fails if the extractors of one of its routes require state that is not
+    /// available to that route.
+    /// ```
+    /// # use routerman::{method::get, request::state::State, router::{Router, RouterBuilder}};
+    /// let admin = Router::builder()
+    ///     .route("/", get(|State(name): State<&'static str>| async move { name }))
+    ///     .with_state("admin");
+    ///
+    /// let router: Router = Router::builder()
+    ///     .route("/", get(|State(name): State<&'static str>| async move { name }))
+    ///     .nest("/admin", admin)
+    ///     .with_state("public")
+    ///     .build();
+    ///
+    /// let missing: RouterBuilder = Router::builder()
+    ///     .route("/", get(|State(name): State<&'static str>| async move { name }));
+    /// assert!(missing.try_build().is_err());
+    /// ```
+    ///
+    /// [`State`]: crate::request::state::State
+    /// [`RequestExt::state`]: crate::request::RequestExt::state
+    pub fn with_state<S>(mut self, state: S) -> Self
+    where
+        S: Clone + Send + Sync + 'static,
+    {
+        let ty = StateType::of::<S>();
+        if self.state.iter().any(|entry| entry.ty == ty) {
+            self.errors
+                .push(BuildErrorKind::DuplicateState { state: ty.name() });
+        }
+
+        self.state.push(StateEntry {
+            ty,
+            insert: Arc::new(move |ext: &mut Extensions| {
+                ext.insert(StateExt(state.clone()));
+            }),
+        });
+        self
+    }
+
+    /// Provide the builder's state to its routes, before merging or nesting it into another
+    /// builder. This keeps the state from leaking into the routes of the outer builder.
+    fn scope_state(mut self) -> Self {
+        if self.state.is_empty() {
+            return self;
+        }
+
+        let state = Arc::<[StateEntry]>::from(std::mem::take(&mut self.state));
+        let provide = |route: Route<Fmt>| {
+            let required = route
+                .required_state()
+                .iter()
+                .filter(|ty| !state.iter().any(|entry| entry.ty == **ty))
+                .copied()
+                .collect::<Vec<_>>();
+
+            let state = state.clone();
+            route
+                .map_request(move |req| {
+                    for entry in state.iter() {
+                        (entry.insert)(req.extensions_mut());
+                    }
+                })
+                .with_required_state(required)
+        };
+
+        for endpoint in &mut self.routes {
+            endpoint.route = provide(endpoint.route.clone());
+        }
+        for (_, route) in &mut self.nested_defaults {
+            *route = provide(route.clone());
+        }
+        self.default = self.default.map(provide);
+        self
+    }
+
+    /// Trust the proxies in a network to report the original client of the requests they forward,
+    /// through the given header. The client is available to handlers through
+    /// [`RequestExt::client_addr`], [`RequestExt::client_scheme`] and [`RequestExt::client_host`].
+    ///
+    /// Only the header configured for the network of the peer is read, since a proxy doesn't strip
+    /// the other headers a client may send. Forwarded addresses are walked from the closest proxy to
+    /// the furthest, so a client can't spoof its address by sending the header itself: the client is
+    /// the first address that isn't trusted. The headers of requests that didn't come from a
+    /// trusted proxy are ignored.
+    ///
+    /// Trusted proxies apply to the whole router, so they must be configured on the outermost
+    /// builder rather than on merged or nested ones.
+    /// ```
+    /// # use routerman::{
+    /// #     method::get,
+    /// #     request::{Request, RequestExt},
+    /// #     router::{ProxyHeader, Router},
+    /// #     test::TestClient,
+    /// # };
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let router: Router = Router::builder()
+    ///     .route(
+    ///         "/",
+    ///         get(|req: Request| async move {
+    ///             format!("{:?} {}", req.client_addr(), req.client_scheme())
+    ///         }),
+    ///     )
+    ///     .trusted_proxy("10.0.0.0/8".parse().unwrap(), ProxyHeader::XForwardedFor)
+    ///     .build();
+    /// let client = TestClient::new(router).remote_addr("10.0.0.2:4000".parse().unwrap());
+    ///
+    /// let res = client
+    ///     .get("/")
+    ///     .header("x-forwarded-for", "203.0.113.7, 198.51.100.1, 10.0.0.1")
+    ///     .header("x-forwarded-proto", "https, http")
+    ///     .send()
+    ///     .await;
+    /// assert_eq!(res.text().await, "Some(198.51.100.1) https");
+    ///
+    /// // Proxies in this network only report clients through `X-Forwarded-For`
+    /// let res = client
+    ///     .get("/")
+    ///     .header("forwarded", "for=203.0.113.7;proto=https")
+    ///     .send()
+    ///     .await;
+    /// assert_eq!(res.text().await, "Some(10.0.0.2) http");
+    /// # }
+    /// ```
+    ///
+    /// [`RequestExt::client_addr`]: crate::request::RequestExt::client_addr
+    /// [`RequestExt::client_scheme`]: crate::request::RequestExt::client_scheme
+    /// [`RequestExt::client_host`]: crate::request::RequestExt::client_host
+    pub fn trusted_proxy(mut self, net: IpNetwork, header: ProxyHeader) -> Self {
+        self.trusted_proxies.push((net, header));
+        self
+    }
+
+    /// Wrap every route added so far, including default routes and the routes of merged or nested
+    /// routers, with a tower [`Layer`]. See [`Route::layer`].
+    ///
+    /// **Only the routes added before calling this method are wrapped.** Routes added afterwards are
+    /// not affected, which allows applying layers to part of a router. Neither are the replies
+    /// generated by the router itself (eg. `404 Not Found` without a default route).
+    /// ```
+    /// # use routerman::{method::get, request::Request, router::Router};
+    /// # use std::time::Duration;
+    /// # use tower::timeout::TimeoutLayer;
+    /// let router: Router = Router::builder()
+    ///     .route("/slow", get(|_req: Request| async { "Done!" }))
+    ///     // Only applies to `/slow`
+    ///     .layer(TimeoutLayer::new(Duration::from_secs(5)))
+    ///     .route("/fast", get(|_req: Request| async { "Done!" }))
+    ///     .build();
+    /// ```
+    pub fn layer<L>(mut self, layer: L) -> Self
+    where
+        L: Layer<RouteService<Fmt>> + Clone + Send + Sync + 'static,
+        L::Service: Service<Request, Response = Response> + Clone + Send + 'static,
+        <L::Service as Service<Request>>::Future: Send + 'static,
+        <L::Service as Service<Request>>::Error: Reply<Fmt>,
+        Fmt: Formatter,
+    {
+        for endpoint in &mut self.routes {
+            endpoint.route = endpoint.route.clone().layer(layer.clone());
+        }
+        for (_, route) in &mut self.nested_defaults {
+            *route = route.clone().layer(layer.clone());
+        }
+        self.default = self.default.map(|route| route.layer(layer));
+        self
+    }
+
+    /// Serve an OpenAPI document describing the whole router at a path. The document is generated
+    /// once, when the router is built. See [`OpenApi::document`].
+    ///
+    /// Calling this method again (or merging a router that also serves a document) replaces the
+    /// previous path.
+    #[cfg(feature = "openapi")]
+    pub fn openapi_route<P>(mut self, path: P, api: OpenApi) -> Self
+    where
+        P: Into<String>,
+    {
+        self.openapi = Some((path.into(), api));
+        self
+    }
+
+    pub fn merge(mut self, router: RouterBuilder<Fmt>) -> Self {
+        let router = router.scope_state();
 
         // Record all the new routes
-        for (path, route) in router.routes {
-            routes.push((path, route));
+        self.routes.extend(router.routes);
+        self.nested_defaults.extend(router.nested_defaults);
+        if !router.trusted_proxies.is_empty() {
+            self.errors.push(BuildErrorKind::NestedTrustedProxy);
+        }
+        #[cfg(feature = "openapi")]
+        if router.openapi.is_some() {
+            self.openapi = router.openapi;
         }
 
         // Merge default routes
         if let Some(route) = router.default {
-            if let Some(_) = default.replace(route) {
-                panic!("cannot merge routers with conflicting default routes")
+            if self.default.replace(route).is_some() {
+                self.errors.push(BuildErrorKind::DefaultConflict);
             }
         }
 
-        Self { routes, default }
+        self.errors.extend(router.errors);
+        self
     }
 
+    /// Mount all the routes of a router under a path prefix.
+    ///
+    /// The nested router's default route only handles unmatched paths under the prefix, including
+    /// paths that only differ from a route by a trailing slash. The prefix is made available to
+    /// handlers through [`RequestExt::nest_prefix`] and [`RequestExt::relative_path`].
+    ///
+    /// The prefix must start with a `/` and cannot contain any parameters.
+    ///
+    /// [`RequestExt::nest_prefix`]: crate::request::RequestExt::nest_prefix
+    /// [`RequestExt::relative_path`]: crate::request::RequestExt::relative_path
+    pub fn nest<P>(mut self, prefix: P, router: RouterBuilder<Fmt>) -> Self
+    where
+        P: Into<String>,
+    {
+        let mut prefix = prefix.into();
+        if !prefix.starts_with('/') || prefix.contains([':', '*']) {
+            self.errors.push(BuildErrorKind::InvalidPrefix { prefix });
+            return self;
+        }
+
+        // Nesting under the root is the same as merging
+        while prefix.ends_with('/') {
+            prefix.pop();
+        }
+        if prefix.is_empty() {
+            return self.merge(router);
+        }
+
+        let router = router.scope_state();
+        let shared_prefix: Arc<str> = Arc::from(&*prefix);
+        self.routes
+            .extend(router.routes.into_iter().map(|endpoint| Endpoint {
+                path: match &*endpoint.path {
+                    "/" => shared_prefix.clone(),
+                    path => Arc::from(format!("{}{}", prefix, path)),
+                },
+                route: endpoint.route,
+                name: endpoint.name,
+                prefix: match endpoint.prefix {
+                    Some(inner) => Some(Arc::from(format!("{}{}", prefix, inner))),
+                    None => Some(shared_prefix.clone()),
+                },
+            }));
+
+        self.nested_defaults.extend(
+            router
+                .nested_defaults
+                .into_iter()
+                .map(|(inner, route)| (format!("{}{}", prefix, inner), route)),
+        );
+        #[cfg(feature = "openapi")]
+        if let Some((path, api)) = router.openapi {
+            self.openapi = Some((format!("{}{}", prefix, path), api));
+        }
+        if let Some(route) = router.default {
+            self.nested_defaults.push((prefix, route));
+        }
+
+        if !router.trusted_proxies.is_empty() {
+            self.errors.push(BuildErrorKind::NestedTrustedProxy);
+        }
+        self.errors.extend(router.errors);
+        self
+    }
+
+    /// Build the router, using the default formatter
+    ///
+    /// # Panics
+    /// Panics if the router configuration is invalid. See [`RouterBuilder::try_build`] for a
+    /// non-panicking version.
     pub fn build(self) -> Router<Fmt>
     where
         Fmt: Default,
     {
+        self.build_with(Fmt::default())
+    }
+
+    /// Build the router, using the provided formatter. The formatter is cloned for every request.
+    ///
+    /// # Panics
+    /// Panics if the router configuration is invalid. See [`RouterBuilder::try_build_with`] for a
+    /// non-panicking version.
+    pub fn build_with(self, formatter: Fmt) -> Router<Fmt> {
+        match self.try_build_with(formatter) {
+            Ok(router) => router,
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    /// Attempt to build the router, using the default formatter
+    pub fn try_build(self) -> Result<Router<Fmt>, BuildError>
+    where
+        Fmt: Default,
+    {
+        self.try_build_with(Fmt::default())
+    }
+
+    /// Attempt to build the router, using the provided formatter. On failure, all the errors in the
+    /// router's configuration are reported, instead of just the first one.
+    pub fn try_build_with(self, formatter: Fmt) -> Result<Router<Fmt>, BuildError> {
+        let Self {
+            routes,
+            default,
+            mut nested_defaults,
+            state,
+            trusted_proxies,
+            #[cfg(feature = "openapi")]
+            openapi,
+            mut errors,
+        } = self;
+
+        #[cfg(feature = "openapi")]
+        let routes = match openapi {
+            Some((path, api)) => {
+                let infos = routes.iter().map(Endpoint::info).collect::<Vec<_>>();
+                let document = api.document(&infos);
+
+                let mut routes = routes;
+                routes.push(Endpoint::openapi(path, document));
+                routes
+            }
+            None => routes,
+        };
+
+        // A failed insertion may leave a matchit router partially modified, so every path is
+        // validated against a scratch router that is rebuilt from the accepted paths after each
+        // failure. The actual router is only built once every path is known to be valid.
+        let mut scratch = matchit::Router::new();
+        let mut accepted = Vec::with_capacity(routes.len());
+        let mut names = HashMap::new();
+        let missing_state = |route: &Route<Fmt>| {
+            route
+                .required_state()
+                .iter()
+                .filter(|ty| !state.iter().any(|entry| entry.ty == **ty))
+                .map(|ty| ty.name())
+                .collect::<Vec<_>>()
+        };
+        for endpoint in &routes {
+            let path = endpoint.path.to_string();
+            for state in missing_state(&endpoint.route) {
+                errors.push(BuildErrorKind::MissingState {
+                    route: format!("route `{}`", path),
+                    state,
+                });
+            }
+            if let Some(name) = &endpoint.name {
+                if names.insert(name.clone(), path.clone()).is_some() {
+                    errors.push(BuildErrorKind::DuplicateName { name: name.clone() });
+                }
+            }
+            match scratch.insert(path.clone(), ()) {
+                Ok(()) => accepted.push(path),
+                Err(err) => {
+                    errors.push(match err {
+                        InsertError::Conflict { with } => BuildErrorKind::Conflict { path, with },
+                        err => BuildErrorKind::InvalidPath { path, source: err },
+                    });
+                    scratch = matchit::Router::new();
+                    for path in &accepted {
+                        scratch
+                            .insert(path.clone(), ())
+                            .expect("accepted paths do not conflict");
+                    }
+                }
+            }
+        }
+
+        for (prefix, route) in &nested_defaults {
+            for state in missing_state(route) {
+                errors.push(BuildErrorKind::MissingState {
+                    route: format!("the default route nested under `{}`", prefix),
+                    state,
+                });
+            }
+        }
+        if let Some(route) = &default {
+            for state in missing_state(route) {
+                errors.push(BuildErrorKind::MissingState {
+                    route: "the default route".to_owned(),
+                    state,
+                });
+            }
+        }
+
+        // Longer prefixes are more specific, so they must be checked first
+        nested_defaults.sort_by(|(a, _), (b, _)| b.len().cmp(&a.len()).then_with(|| a.cmp(b)));
+        for pair in nested_defaults.windows(2) {
+            if pair[0].0 == pair[1].0 {
+                errors.push(BuildErrorKind::NestedDefaultConflict {
+                    prefix: pair[0].0.clone(),
+                });
+            }
+        }
+
+        if !errors.is_empty() {
+            return Err(BuildError { errors });
+        }
+
+        // Layered routes create their services from the router's formatter
         let mut inner = matchit::Router::new();
-        for (path, route) in self.routes.into_iter() {
-            inner.insert(path, route).expect("insert route");
+        let mut infos = Vec::with_capacity(routes.len());
+        for mut endpoint in routes {
+            infos.push(endpoint.info());
+            endpoint.route = endpoint.route.bind(&formatter);
+            inner
+                .insert(endpoint.path.to_string(), endpoint)
+                .expect("paths were validated");
         }
 
-        Router {
+        Ok(Router {
             inner: Arc::new(RouterImpl {
                 inner,
-                default: self.default,
+                default: default.map(|route| route.bind(&formatter)),
+                nested_defaults: nested_defaults
+                    .into_iter()
+                    .map(|(prefix, route)| (Arc::from(prefix), route.bind(&formatter)))
+                    .collect(),
+                state: state.into_iter().map(|entry| entry.insert).collect(),
+                names: Arc::new(RouteNames(names)),
+                trusted_proxies,
+                routes: infos,
             }),
-            formatter: Fmt::default(), // TODO: Non-default formatter
+            formatter,
+        })
+    }
+}
+
+/// Error building a router. Contains every error found in the router's configuration.
+#[derive(Debug)]
+pub struct BuildError {
+    pub errors: Vec<BuildErrorKind>,
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid router configuration")?;
+        for err in &self.errors {
+            write!(f, "\n  - {}", err)?;
         }
+        Ok(())
     }
 }
 
-impl<Fmt> Service<&AddrStream> for Router<Fmt>
+impl std::error::Error for BuildError {}
+
+#[derive(Debug, Error)]
+pub enum BuildErrorKind {
+    /// A route conflicts with a previously registered route
+    #[error("route `{path}` conflicts with `{with}`")]
+    Conflict { path: String, with: String },
+
+    /// A route's path is not a valid pattern
+    #[error("invalid route `{path}`: {source}")]
+    InvalidPath {
+        path: String,
+        #[source]
+        source: InsertError,
+    },
+
+    /// More than one default route was provided when merging routers
+    #[error("cannot merge routers with conflicting default routes")]
+    DefaultConflict,
+
+    /// More than one default route was nested under the same prefix
+    #[error("conflicting default routes nested under `{prefix}`")]
+    NestedDefaultConflict { prefix: String },
+
+    /// A nest prefix does not start with a `/` or contains parameters
+    #[error("invalid nest prefix `{prefix}`")]
+    InvalidPrefix { prefix: String },
+
+    /// More than one route was registered with the same name
+    #[error("duplicate route name `{name}`")]
+    DuplicateName { name: String },
+
+    /// State of the same type was provided to a builder more than once
+    #[error("duplicate state of type `{state}`")]
+    DuplicateState { state: &'static str },
+
+    /// A route's extractors require state that was not provided to it. `route` describes the route
+    /// (eg. ``route `/users/:id` ``).
+    #[error("missing state `{state}` required by {route}")]
+    MissingState { route: String, state: &'static str },
+
+    /// Trusted proxies were configured on a merged or nested router, rather than on the outermost
+    /// one
+    #[error("trusted proxies must be configured on the outermost router")]
+    NestedTrustedProxy,
+}
+
+/// Characters that must be percent-encoded within a path segment
+const SEGMENT: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'%')
+    .add(b'/')
+    .add(b'<')
+    .add(b'>')
+    .add(b'?')
+    .add(b'`')
+    .add(b'{')
+    .add(b'}');
+
+/// Characters that must be percent-encoded within a catch-all parameter, which may span multiple
+/// path segments
+const CATCH_ALL: &AsciiSet = &SEGMENT.remove(b'/');
+
+/// Paths of the named routes of a router
+pub(crate) struct RouteNames(HashMap<String, String>);
+
+impl RouteNames {
+    pub(crate) fn url_for(
+        &self,
+        name: &str,
+        params: &[(&str, &str)],
+    ) -> Result<String, UrlForError> {
+        let path = self
+            .0
+            .get(name)
+            .ok_or_else(|| UrlForError::UnknownRoute(name.to_owned()))?;
+
+        let mut used = vec![false; params.len()];
+        let mut url = String::with_capacity(path.len());
+        for token in pattern::tokenize(path) {
+            let (key, set) = match token {
+                Token::Static(text) => {
+                    url.push_str(text);
+                    continue;
+                }
+                Token::Param(key) => (key, SEGMENT),
+                Token::CatchAll(key) => (key, CATCH_ALL),
+            };
+
+            let idx = params.iter().position(|(k, _)| *k == key).ok_or_else(|| {
+                UrlForError::MissingParam {
+                    name: name.to_owned(),
+                    param: key.to_owned(),
+                }
+            })?;
+            used[idx] = true;
+            url.extend(utf8_percent_encode(params[idx].1, set));
+        }
+
+        // Every parameter must have been used exactly once
+        match used.iter().position(|used| !used) {
+            Some(idx) => Err(UrlForError::ExtraParam {
+                name: name.to_owned(),
+                param: params[idx].0.to_owned(),
+            }),
+            None => Ok(url),
+        }
+    }
+}
+
+/// Error generating the url of a named route
+#[derive(Debug, Error)]
+pub enum UrlForError {
+    /// No route with the given name exists
+    #[error("unknown route `{0}`")]
+    UnknownRoute(String),
+
+    /// A parameter of the route's path was not provided
+    #[error("missing parameter `{param}` for route `{name}`")]
+    MissingParam { name: String, param: String },
+
+    /// A parameter was provided that is not part of the route's path, or was provided twice
+    #[error("unexpected parameter `{param}` for route `{name}`")]
+    ExtraParam { name: String, param: String },
+}
+
+impl<Fmt, C> Service<&C> for Router<Fmt>
 where
     Fmt: Clone,
+    C: Connected,
 {
     type Response = RequestService<Fmt>;
     type Error = Infallible;
@@ -121,22 +1032,44 @@ where
         Poll::Ready(Ok(()))
     }
 
-    fn call(&mut self, conn: &AddrStream) -> Self::Future {
-        let remote_addr = conn.remote_addr();
-        std::future::ready(Ok(RequestService {
-            remote_addr,
+    fn call(&mut self, conn: &C) -> Self::Future {
+        std::future::ready(Ok(self.connection_service(conn)))
+    }
+}
+
+impl<Fmt: Clone> Router<Fmt> {
+    /// Service handling the requests of a single connection
+    pub(crate) fn connection_service(&self, conn: &impl Connected) -> RequestService<Fmt> {
+        RequestService {
+            connection: conn.connection_info(),
+            #[cfg(feature = "tls")]
+            tls: conn.tls_info(),
             router: self.inner.clone(),
             formatter: self.formatter.clone(),
-        }))
+        }
     }
 }
 
 pub struct RequestService<Fmt> {
     formatter: Fmt,
-    remote_addr: SocketAddr,
+    connection: ConnectionInfo,
+    #[cfg(feature = "tls")]
+    tls: Option<TlsInfo>,
     router: Arc<RouterImpl<Fmt>>,
 }
 
+impl<Fmt: Clone> Clone for RequestService<Fmt> {
+    fn clone(&self) -> Self {
+        Self {
+            formatter: self.formatter.clone(),
+            connection: self.connection.clone(),
+            #[cfg(feature = "tls")]
+            tls: self.tls.clone(),
+            router: self.router.clone(),
+        }
+    }
+}
+
 #[derive(Error)]
 #[error("{kind}")]
 pub struct RouteError {
@@ -173,8 +1106,7 @@ pub enum RouteErrorKind {
 
 impl<Fmt> Service<hyper::Request<Body>> for RequestService<Fmt>
 where
-    RouteError: Reply<Fmt>,
-    Fmt: Clone,
+    Fmt: Formatter,
 {
     type Response = Response;
     type Error = Infallible;
@@ -187,47 +1119,105 @@ where
     }
 
     fn call(&mut self, mut req: hyper::Request<Body>) -> Self::Future {
-        // Add connection address information to the request's extensions
-        req.extensions_mut().insert(RemoteAddrExt(self.remote_addr));
+        // Add connection information to the request's extensions
+        req.extensions_mut().insert(self.connection.clone());
+        #[cfg(feature = "tls")]
+        if let Some(tls) = &self.tls {
+            req.extensions_mut().insert(tls.clone());
+        }
 
-        let res = match self.router.inner.at(req.uri().path()) {
-            // A route was found. Attempt to parse the parameters and run the handler. If the
-            // parameters are invalid (eg. invalid percent-encoded utf8), reply with error.
-            Ok(route) => match RouteParamsExt::try_from(route.params) {
-                Ok(params) => Ok((route.value, Some(params))),
-                Err(err) => Err(RouteErrorKind::Param(err)),
-            },
-            // No route was found. Use the fallback if it exists, otherwise reply with error.
-            Err(MatchError::NotFound) => match self.router.default {
-                Some(ref route) => Ok((route, None)),
-                None => Err(RouteErrorKind::NotFound),
-            },
+        self.router.dispatch(req, self.formatter.clone())
+    }
+}
 
-            // There was either a trailing slash when there shouldn't be, or there wasn't a trailing
-            // slash when there should be. Reply with an error that Indicates to redirect the user
-            // to the correct path.
-            Err(MatchError::ExtraTrailingSlash) => Err(RouteErrorKind::ExtraTrailingSlash),
-            Err(MatchError::MissingTrailingSlash) => Err(RouteErrorKind::MissingTrailingSlash),
-        };
+/// Route requests directly, without a connection. This allows mounting a router within another
+/// router using [`Route::from_service`]. Connection information (eg. the remote address) is
+/// expected to already be present in the request's extensions.
+///
+/// When mounted, the router only matches the part of the path that the outer route didn't match:
+/// The request's uri is rewritten to the value of the outer route's trailing catch-all parameter,
+/// or to the path relative to the prefix of the outer nested router for default routes.
+/// ```
+/// # use routerman::{method::get, route::Route, router::Router, test::TestClient};
+/// # #[tokio::main]
+/// # async fn main() {
+/// let legacy: Router = Router::builder()
+///     .route("/users", get(|| async { "Legacy users" }))
+///     .build();
+/// let router: Router = Router::builder()
+///     .route("/legacy/*path", Route::from_service(legacy))
+///     .build();
+///
+/// let res = TestClient::new(router).get("/legacy/users").send().await;
+/// assert_eq!(res.text().await, "Legacy users");
+/// # }
+/// ```
+impl<Fmt> Service<hyper::Request<Body>> for Router<Fmt>
+where
+    Fmt: Formatter,
+{
+    type Response = Response;
+    type Error = Infallible;
+    type Future = RequestFuture;
 
-        // Finally return the request future, either containing the route's future or an immediate
-        // reponse
-        match res {
-            Ok((route, params)) => {
-                if let Some(params) = params {
-                    req.extensions_mut().insert(params);
-                }
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
 
-                RequestFuture::Route((route.handler_fn())(req, self.formatter.clone()))
-            }
-            Err(err) => RequestFuture::Response(Some(
-                RouteError {
-                    request: req,
-                    kind: err,
+    fn call(&mut self, mut req: hyper::Request<Body>) -> Self::Future {
+        if let Some(path) = mounted_path(&req) {
+            let path_and_query = match (path, req.uri().query()) {
+                ("", Some(query)) => format!("/?{}", query),
+                ("", None) => "/".to_owned(),
+                (path, Some(query)) => format!("{}?{}", path, query),
+                (path, None) => path.to_owned(),
+            };
+
+            let mut parts = req.uri().clone().into_parts();
+            parts.path_and_query = Some(
+                path_and_query
+                    .parse()
+                    .expect("the end of a valid path is a valid path"),
+            );
+            *req.uri_mut() = Uri::from_parts(parts).expect("only the path was changed");
+
+            // Remove the outer router's routing information
+            let extensions = req.extensions_mut();
+            extensions.remove::<MatchedPath>();
+            extensions.remove::<NestPrefixExt>();
+            extensions.remove::<RouteParamsExt>();
+        }
+
+        self.inner.dispatch(req, self.formatter.clone())
+    }
+}
+
+/// Part of the path of a request that was not matched by the outer router, if the request is
+/// being handled by a router mounted within another router
+fn mounted_path(req: &Request) -> Option<&str> {
+    let path = req.uri().path();
+    match req.extensions().get::<MatchedPath>()? {
+        MatchedPath::Route(pattern) => {
+            // Every segment before the catch-all parameter was matched by the outer route. The
+            // unmatched part starts at the `/` preceding the catch-all.
+            let mut slashes = 0;
+            for token in pattern::tokenize(pattern) {
+                match token {
+                    Token::Static(text) => slashes += text.matches('/').count(),
+                    Token::Param(_) => {}
+                    Token::CatchAll(_) => {
+                        let matched = path
+                            .match_indices('/')
+                            .nth(slashes.saturating_sub(1))
+                            .map_or(path.len(), |(i, _)| i);
+                        return Some(&path[matched..]);
+                    }
                 }
-                .reply(self.formatter.clone()),
-            )),
+            }
+            Some("")
         }
+        MatchedPath::Default(Some(prefix)) => path.strip_prefix(&**prefix),
+        MatchedPath::Default(None) => None,
     }
 }
 
@@ -242,7 +1232,7 @@ impl Future for RequestFuture {
 
     fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
         match self.as_mut().project() {
-            RequestFutureProj::Route(fut) => return Poll::Ready(Ok(ready!(fut.poll(cx)))),
+            RequestFutureProj::Route(fut) => Poll::Ready(Ok(ready!(fut.poll(cx)))),
             RequestFutureProj::Response(res @ Some(_)) => Poll::Ready(Ok(res.take().unwrap())),
             RequestFutureProj::Response(None) => panic!("future polled after completion"),
         }
@@ -0,0 +1,440 @@
+//! Server runner with graceful shutdown
+//!
+//! [`serve`] binds a router to one or more addresses and serves it until a shutdown signal, by
+//! default ctrl-c or `SIGTERM`. Once signalled, the server stops accepting connections and waits
+//! for the requests in flight to complete, for at most the drain timeout. Requests are in flight
+//! until their response's body has been sent.
+//! ```no_run
+//! # use routerman::{method::get, router::Router};
+//! # use std::time::Duration;
+//! # #[tokio::main]
+//! # async fn main() {
+//! let router: Router = Router::builder()
+//!     .route("/", get(|| async { "Hello, World!" }))
+//!     .build();
+//!
+//! let report = routerman::serve(router)
+//!     .bind(([0, 0, 0, 0], 8080))
+//!     .bind(([0, 0, 0, 0, 0, 0, 0, 0], 8080))
+//!     .drain_timeout(Duration::from_secs(30))
+//!     .run()
+//!     .await
+//!     .unwrap();
+//!
+//! if report.timed_out() {
+//!     eprintln!("{} requests were still in flight", report.in_flight());
+//! }
+//! # }
+//! ```
+
+use crate::{
+    connection::Connected,
+    response::{Formatter, Response},
+    route::BoxFuture,
+    router::{RequestService, Router},
+};
+use futures_util::{
+    future::{self, Either, FutureExt, Shared},
+    pin_mut,
+};
+use hyper::{
+    body::{Bytes, HttpBody, SizeHint},
+    server::{accept::Accept, conn::AddrIncoming},
+    Body, HeaderMap, Request, Server,
+};
+use std::{
+    convert::Infallible,
+    error::Error as StdError,
+    future::{Future, Ready},
+    net::SocketAddr,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    task::{Context, Poll},
+    time::Duration,
+};
+use thiserror::Error;
+use tokio::{
+    io::{AsyncRead, AsyncWrite},
+    task::JoinSet,
+};
+use tower_service::Service;
+
+/// Start configuring a server for a router. See the [module documentation](self).
+pub fn serve<Fmt: Formatter>(router: Router<Fmt>) -> Serve<Fmt> {
+    Serve {
+        router,
+        listeners: Vec::new(),
+        signal: Box::pin(shutdown_signal()),
+        drain_timeout: None,
+        in_flight: InFlight::default(),
+    }
+}
+
+/// Server being configured by [`serve`]
+#[must_use = "servers do nothing unless run"]
+pub struct Serve<Fmt> {
+    router: Router<Fmt>,
+    listeners: Vec<Listener<Fmt>>,
+    signal: BoxFuture<()>,
+    drain_timeout: Option<Duration>,
+    in_flight: InFlight,
+}
+
+/// Source of the connections of a server
+enum Listener<Fmt> {
+    /// TCP address, bound once the server runs
+    Addr(SocketAddr),
+
+    /// Incoming stream of connections, started once the server runs
+    Incoming(Box<StartServer<Fmt>>),
+}
+
+type StartServer<Fmt> = dyn FnOnce(&ServerContext<Fmt>) -> ServerFuture + Send;
+type ServerFuture = BoxFuture<Result<(), hyper::Error>>;
+
+impl<Fmt: Formatter> Serve<Fmt> {
+    /// Add an address to listen on
+    pub fn bind(mut self, addr: impl Into<SocketAddr>) -> Self {
+        self.listeners.push(Listener::Addr(addr.into()));
+        self
+    }
+
+    /// Add an incoming stream of connections to serve, such as a Unix domain socket listener or a
+    /// [`TlsIncoming`]. See [`connection`](crate::connection).
+    ///
+    /// [`TlsIncoming`]: crate::tls::TlsIncoming
+    pub fn bind_incoming<I>(mut self, incoming: I) -> Self
+    where
+        I: Accept + Send + 'static,
+        I::Conn: Connected + AsyncRead + AsyncWrite + Unpin + Send + 'static,
+        I::Error: Into<Box<dyn StdError + Send + Sync>>,
+    {
+        self.listeners
+            .push(Listener::Incoming(Box::new(move |ctx| ctx.serve(incoming))));
+        self
+    }
+
+    /// Shut down once a future completes, instead of on ctrl-c or `SIGTERM`
+    /// ```
+    /// # use routerman::router::Router;
+    /// # use std::time::Duration;
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let router: Router = Router::builder().build();
+    /// let report = routerman::serve(router)
+    ///     .bind(([127, 0, 0, 1], 0))
+    ///     .with_graceful_shutdown(tokio::time::sleep(Duration::from_millis(10)))
+    ///     .run()
+    ///     .await
+    ///     .unwrap();
+    /// assert!(!report.timed_out());
+    /// # }
+    /// ```
+    pub fn with_graceful_shutdown<F>(mut self, signal: F) -> Self
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        self.signal = Box::pin(signal);
+        self
+    }
+
+    /// Maximum time to wait for the requests in flight once shut down. Waits indefinitely by
+    /// default.
+    ///
+    /// Connections still open once the timeout elapses are aborted, dropping the requests they
+    /// were processing.
+    pub fn drain_timeout(mut self, timeout: Duration) -> Self {
+        self.drain_timeout = Some(timeout);
+        self
+    }
+
+    /// Counter of the requests being processed, which can be read while the server runs
+    pub fn in_flight(&self) -> InFlight {
+        self.in_flight.clone()
+    }
+
+    /// Bind every address and serve the router until shut down
+    pub async fn run(self) -> Result<ShutdownReport, ServeError> {
+        if self.listeners.is_empty() {
+            return Err(ServeError::NoAddress);
+        }
+
+        // Connections are spawned onto a task set, so they can be aborted once the drain timeout
+        // elapses
+        let ctx = ServerContext {
+            router: self.router,
+            in_flight: self.in_flight,
+            signal: self.signal.shared(),
+            tasks: ConnectionTasks::default(),
+        };
+        let mut servers = Vec::with_capacity(self.listeners.len());
+        for listener in self.listeners {
+            servers.push(match listener {
+                Listener::Addr(addr) => ctx.serve(
+                    AddrIncoming::bind(&addr)
+                        .map_err(|source| ServeError::Bind { addr, source })?,
+                ),
+                Listener::Incoming(start) => start(&ctx),
+            });
+        }
+
+        // Servers only complete on their own if they fail
+        let servers = future::try_join_all(servers);
+        pin_mut!(servers);
+        let servers = match future::select(servers, ctx.signal.clone()).await {
+            Either::Left((res, _)) => res.map(|_| servers_drained()),
+            Either::Right(((), servers)) => match self.drain_timeout {
+                Some(timeout) => match tokio::time::timeout(timeout, servers).await {
+                    Ok(res) => res.map(|_| servers_drained()),
+                    Err(_) => {
+                        let in_flight = ctx.in_flight.get();
+                        ctx.tasks.abort_all();
+                        Ok(ShutdownReport {
+                            in_flight,
+                            timed_out: true,
+                        })
+                    }
+                },
+                None => servers.await.map(|_| servers_drained()),
+            },
+        };
+        servers.map_err(ServeError::Server)
+    }
+}
+
+/// Everything the servers of a running [`Serve`] share
+struct ServerContext<Fmt> {
+    router: Router<Fmt>,
+    in_flight: InFlight,
+    signal: Shared<BoxFuture<()>>,
+    tasks: ConnectionTasks,
+}
+
+impl<Fmt: Formatter> ServerContext<Fmt> {
+    /// Serve the router over an incoming stream of connections until signalled
+    fn serve<I>(&self, incoming: I) -> ServerFuture
+    where
+        I: Accept + Send + 'static,
+        I::Conn: Connected + AsyncRead + AsyncWrite + Unpin + Send + 'static,
+        I::Error: Into<Box<dyn StdError + Send + Sync>>,
+    {
+        let make_service = MakeTrackedService {
+            router: self.router.clone(),
+            in_flight: self.in_flight.clone(),
+        };
+        Box::pin(
+            Server::builder(incoming)
+                .executor(self.tasks.clone())
+                .serve(make_service)
+                .with_graceful_shutdown(self.signal.clone()),
+        )
+    }
+}
+
+fn servers_drained() -> ShutdownReport {
+    ShutdownReport {
+        in_flight: 0,
+        timed_out: false,
+    }
+}
+
+/// Completes on ctrl-c or, on unix, `SIGTERM`
+pub async fn shutdown_signal() {
+    let ctrl_c = async {
+        if tokio::signal::ctrl_c().await.is_err() {
+            future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                terminate.recv().await;
+            }
+            Err(_) => future::pending::<()>().await,
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = future::pending::<()>();
+
+    pin_mut!(ctrl_c, terminate);
+    future::select(ctrl_c, terminate).await;
+}
+
+/// Number of requests being processed by a server
+#[derive(Debug, Clone, Default)]
+pub struct InFlight(Arc<AtomicUsize>);
+
+impl InFlight {
+    pub fn get(&self) -> usize {
+        self.0.load(Ordering::SeqCst)
+    }
+
+    fn start(&self) -> InFlightGuard {
+        self.0.fetch_add(1, Ordering::SeqCst);
+        InFlightGuard(self.clone())
+    }
+}
+
+/// Marks a request as completed when dropped
+struct InFlightGuard(InFlight);
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        self.0 .0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Outcome of a server's shutdown
+#[derive(Debug, Clone, Copy)]
+pub struct ShutdownReport {
+    in_flight: usize,
+    timed_out: bool,
+}
+
+impl ShutdownReport {
+    /// Requests still being processed when the server stopped waiting for them
+    pub fn in_flight(&self) -> usize {
+        self.in_flight
+    }
+
+    /// Whether the drain timeout elapsed before every connection was closed
+    pub fn timed_out(&self) -> bool {
+        self.timed_out
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ServeError {
+    #[error("no address to serve on")]
+    NoAddress,
+
+    #[error("failed to bind {addr}: {source}")]
+    Bind {
+        addr: SocketAddr,
+        source: hyper::Error,
+    },
+
+    #[error("server error: {0}")]
+    Server(#[source] hyper::Error),
+}
+
+/// Executor spawning the connections of a server onto a task set
+#[derive(Clone, Default)]
+struct ConnectionTasks(Arc<Mutex<JoinSet<()>>>);
+
+impl ConnectionTasks {
+    fn abort_all(&self) {
+        self.0.lock().unwrap().abort_all();
+    }
+}
+
+impl<F> hyper::rt::Executor<F> for ConnectionTasks
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    fn execute(&self, fut: F) {
+        let mut tasks = self.0.lock().unwrap();
+
+        // Completed tasks are kept by the set until joined
+        while tasks.try_join_next().is_some() {}
+        tasks.spawn(fut.map(drop));
+    }
+}
+
+struct MakeTrackedService<Fmt> {
+    router: Router<Fmt>,
+    in_flight: InFlight,
+}
+
+impl<Fmt: Clone, C: Connected> Service<&C> for MakeTrackedService<Fmt> {
+    type Response = TrackedService<Fmt>;
+    type Error = Infallible;
+    type Future = Ready<Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, conn: &C) -> Self::Future {
+        std::future::ready(Ok(TrackedService {
+            inner: self.router.connection_service(conn),
+            in_flight: self.in_flight.clone(),
+        }))
+    }
+}
+
+/// Request service counting the requests in flight
+struct TrackedService<Fmt> {
+    inner: RequestService<Fmt>,
+    in_flight: InFlight,
+}
+
+impl<Fmt: Formatter> Service<Request<Body>> for TrackedService<Fmt> {
+    type Response = hyper::Response<TrackedBody>;
+    type Error = Infallible;
+    type Future = BoxFuture<Result<Self::Response, Infallible>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<Body>) -> Self::Future {
+        let guard = self.in_flight.start();
+        let fut = self.inner.call(req);
+        Box::pin(async move {
+            let res: Response = fut.await?;
+            Ok(res.map(|body| TrackedBody {
+                inner: body,
+                guard: Some(guard),
+            }))
+        })
+    }
+}
+
+/// Response body keeping its request in flight until it has been sent, or dropped
+struct TrackedBody {
+    inner: Body,
+    guard: Option<InFlightGuard>,
+}
+
+impl HttpBody for TrackedBody {
+    type Data = Bytes;
+    type Error = hyper::Error;
+
+    fn poll_data(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Self::Data, Self::Error>>> {
+        let res = Pin::new(&mut self.inner).poll_data(cx);
+        if let Poll::Ready(None | Some(Err(_))) = res {
+            self.guard = None;
+        }
+        res
+    }
+
+    fn poll_trailers(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<Option<HeaderMap>, Self::Error>> {
+        let res = Pin::new(&mut self.inner).poll_trailers(cx);
+        if res.is_ready() {
+            self.guard = None;
+        }
+        res
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        self.inner.size_hint()
+    }
+}
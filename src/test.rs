@@ -0,0 +1,227 @@
+//! Utilities for testing routers without binding to a socket
+//!
+//! [`TestClient`] sends requests directly to a router, as if they had arrived through a
+//! connection.
+//! ```
+//! # use routerman::{method::get, router::Router, test::TestClient};
+//! # use hyper::StatusCode;
+//! # #[tokio::main]
+//! # async fn main() {
+//! let router: Router = Router::builder()
+//!     .route("/hello", get(|| async { "Hello, World!" }))
+//!     .build();
+//! let client = TestClient::new(router);
+//!
+//! let res = client.get("/hello").header("accept", "text/plain").send().await;
+//! assert_eq!(res.status(), StatusCode::OK);
+//! assert_eq!(res.text().await, "Hello, World!");
+//!
+//! let res = client.post("/hello").send().await;
+//! assert_eq!(res.status(), StatusCode::METHOD_NOT_ALLOWED);
+//! # }
+//! ```
+
+use crate::{
+    connection::ConnectionInfo,
+    response::{DefaultFormatter, Formatter, Response},
+    router::Router,
+};
+use hyper::{
+    body::{self, Bytes},
+    header::{HeaderMap, HeaderName, HeaderValue},
+    http::request::Builder,
+    Body, Method, Request, StatusCode,
+};
+use std::net::{Ipv4Addr, SocketAddr};
+use tower_service::Service;
+
+/// Client sending requests directly to a router
+pub struct TestClient<Fmt = DefaultFormatter> {
+    router: Router<Fmt>,
+    remote_addr: SocketAddr,
+}
+
+impl<Fmt: Formatter> TestClient<Fmt> {
+    /// Create a client for a router. Requests appear to come from `127.0.0.1:12345`, unless
+    /// changed with [`TestClient::remote_addr`].
+    pub fn new(router: Router<Fmt>) -> Self {
+        Self {
+            router,
+            remote_addr: SocketAddr::from((Ipv4Addr::LOCALHOST, 12345)),
+        }
+    }
+
+    /// Set the remote address requests appear to come from
+    pub fn remote_addr(mut self, remote_addr: SocketAddr) -> Self {
+        self.remote_addr = remote_addr;
+        self
+    }
+
+    /// Start building a request with any method
+    pub fn request(&self, method: Method, uri: &str) -> TestRequest<'_, Fmt> {
+        TestRequest {
+            client: self,
+            builder: Request::builder().method(method).uri(uri),
+            body: Body::empty(),
+        }
+    }
+
+    pub fn get(&self, uri: &str) -> TestRequest<'_, Fmt> {
+        self.request(Method::GET, uri)
+    }
+
+    pub fn post(&self, uri: &str) -> TestRequest<'_, Fmt> {
+        self.request(Method::POST, uri)
+    }
+
+    pub fn put(&self, uri: &str) -> TestRequest<'_, Fmt> {
+        self.request(Method::PUT, uri)
+    }
+
+    pub fn patch(&self, uri: &str) -> TestRequest<'_, Fmt> {
+        self.request(Method::PATCH, uri)
+    }
+
+    pub fn delete(&self, uri: &str) -> TestRequest<'_, Fmt> {
+        self.request(Method::DELETE, uri)
+    }
+
+    pub fn head(&self, uri: &str) -> TestRequest<'_, Fmt> {
+        self.request(Method::HEAD, uri)
+    }
+
+    pub fn options(&self, uri: &str) -> TestRequest<'_, Fmt> {
+        self.request(Method::OPTIONS, uri)
+    }
+}
+
+/// Request being built by a [`TestClient`]
+///
+/// Invalid request parts (eg. header values) are only reported when the request is sent, by
+/// panicking.
+#[must_use = "requests do nothing unless sent"]
+pub struct TestRequest<'a, Fmt> {
+    client: &'a TestClient<Fmt>,
+    builder: Builder,
+    body: Body,
+}
+
+impl<'a, Fmt: Formatter> TestRequest<'a, Fmt> {
+    pub fn header<K, V>(mut self, key: K, value: V) -> Self
+    where
+        HeaderName: TryFrom<K>,
+        <HeaderName as TryFrom<K>>::Error: Into<hyper::http::Error>,
+        HeaderValue: TryFrom<V>,
+        <HeaderValue as TryFrom<V>>::Error: Into<hyper::http::Error>,
+    {
+        self.builder = self.builder.header(key, value);
+        self
+    }
+
+    pub fn body(mut self, body: impl Into<Body>) -> Self {
+        self.body = body.into();
+        self
+    }
+
+    /// Send a json body, setting the `Content-Type` header
+    ///
+    /// # Panics
+    /// Panics if the value cannot be serialized.
+    #[cfg(feature = "json")]
+    pub fn json<T: serde::Serialize + ?Sized>(self, value: &T) -> Self {
+        let body = serde_json::to_vec(value).expect("failed to serialize json body");
+        self.header(
+            hyper::header::CONTENT_TYPE,
+            crate::mime::APPLICATION_JSON.header(),
+        )
+        .body(body)
+    }
+
+    /// Send a url-encoded form body, setting the `Content-Type` header
+    ///
+    /// # Panics
+    /// Panics if the value cannot be serialized.
+    #[cfg(feature = "form")]
+    pub fn form<T: serde::Serialize + ?Sized>(self, value: &T) -> Self {
+        let body = serde_html_form::to_string(value).expect("failed to serialize form body");
+        self.header(
+            hyper::header::CONTENT_TYPE,
+            crate::mime::APPLICATION_WWW_FORM_URLENCODED.header(),
+        )
+        .body(body)
+    }
+
+    /// Send the request to the router and wait for its response
+    ///
+    /// # Panics
+    /// Panics if the request is invalid.
+    pub async fn send(self) -> TestResponse {
+        let mut req = self
+            .builder
+            .body(self.body)
+            .expect("failed to build test request");
+        req.extensions_mut()
+            .insert(ConnectionInfo::Tcp(self.client.remote_addr));
+
+        let res = match self.client.router.clone().call(req).await {
+            Ok(res) => res,
+            Err(err) => match err {},
+        };
+        TestResponse { inner: res }
+    }
+}
+
+/// Response received by a [`TestClient`]
+pub struct TestResponse {
+    inner: Response,
+}
+
+impl TestResponse {
+    pub fn status(&self) -> StatusCode {
+        self.inner.status()
+    }
+
+    pub fn headers(&self) -> &HeaderMap {
+        self.inner.headers()
+    }
+
+    /// Value of a header, if it is present and valid utf-8
+    pub fn header(&self, key: impl AsRef<str>) -> Option<&str> {
+        self.inner
+            .headers()
+            .get(key.as_ref())
+            .and_then(|value| value.to_str().ok())
+    }
+
+    /// Collect the response body
+    ///
+    /// # Panics
+    /// Panics if the body cannot be read.
+    pub async fn bytes(self) -> Bytes {
+        body::to_bytes(self.inner.into_body())
+            .await
+            .expect("failed to read response body")
+    }
+
+    /// Collect the response body as a string
+    ///
+    /// # Panics
+    /// Panics if the body cannot be read or is not valid utf-8.
+    pub async fn text(self) -> String {
+        String::from_utf8(self.bytes().await.to_vec()).expect("response body is not valid utf-8")
+    }
+
+    /// Deserialize the response body from json
+    ///
+    /// # Panics
+    /// Panics if the body cannot be read or deserialized.
+    #[cfg(feature = "json")]
+    pub async fn json<T: serde::de::DeserializeOwned>(self) -> T {
+        serde_json::from_slice(&self.bytes().await).expect("failed to deserialize json body")
+    }
+
+    /// The underlying response
+    pub fn into_inner(self) -> Response {
+        self.inner
+    }
+}
@@ -0,0 +1,258 @@
+//! HTTPS serving through rustls
+//!
+//! [`TlsIncoming`] performs TLS handshakes on the connections of an incoming stream, producing
+//! connections that a [`Router`](crate::router::Router) can serve. Details about the TLS session
+//! are available to handlers through [`RequestExt::tls_info`].
+//! ```no_run
+//! # use routerman::{method::get, router::Router, tls::{self, TlsIncoming}};
+//! # use hyper::Server;
+//! # #[tokio::main]
+//! # async fn main() {
+//! let config = tls::server_config(
+//!     &std::fs::read("cert.pem").unwrap(),
+//!     &std::fs::read("key.pem").unwrap(),
+//! )
+//! .unwrap();
+//!
+//! let router: Router = Router::builder()
+//!     .route("/", get(|| async { "Hello, World!" }))
+//!     .build();
+//!
+//! let incoming = TlsIncoming::bind(&([0, 0, 0, 0], 8443).into(), config.into()).unwrap();
+//! Server::builder(incoming).serve(router).await.unwrap();
+//! # }
+//! ```
+//!
+//! [`RequestExt::tls_info`]: crate::request::RequestExt::tls_info
+
+use crate::connection::{Connected, ConnectionInfo};
+use futures_util::{stream::FuturesUnordered, StreamExt};
+use hyper::server::{accept::Accept, conn::AddrIncoming};
+use pin_project::pin_project;
+use std::{
+    io,
+    net::SocketAddr,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+    time::Duration,
+};
+use thiserror::Error;
+use tokio::{
+    io::{AsyncRead, AsyncWrite},
+    time::Timeout,
+};
+use tokio_rustls::{
+    rustls::{
+        crypto::ring,
+        pki_types::{CertificateDer, PrivateKeyDer},
+        ProtocolVersion, ServerConfig, ServerConnection,
+    },
+    TlsAcceptor,
+};
+
+pub use tokio_rustls::{rustls, server::TlsStream};
+
+/// Incoming stream of TLS connections, wrapping an incoming stream of plain connections
+///
+/// Handshakes progress concurrently, so a slow client doesn't hold back the connections behind
+/// it. Connections whose handshake fails or times out are dropped without affecting the server.
+/// Once [`max_handshakes`](Self::max_handshakes) are in progress, no connection is accepted until
+/// one of them completes.
+#[pin_project]
+pub struct TlsIncoming<I: Accept = AddrIncoming> {
+    #[pin]
+    incoming: I,
+    incoming_done: bool,
+    acceptor: TlsAcceptor,
+    handshake_timeout: Duration,
+    max_handshakes: usize,
+    handshakes: FuturesUnordered<Timeout<tokio_rustls::Accept<I::Conn>>>,
+}
+
+impl TlsIncoming {
+    /// Bind to a TCP address, accepting TLS connections with the given configuration
+    pub fn bind(addr: &SocketAddr, config: Arc<ServerConfig>) -> Result<Self, hyper::Error> {
+        Ok(Self::new(AddrIncoming::bind(addr)?, config))
+    }
+}
+
+impl<I: Accept> TlsIncoming<I> {
+    pub fn new(incoming: I, config: Arc<ServerConfig>) -> Self {
+        Self {
+            incoming,
+            incoming_done: false,
+            acceptor: TlsAcceptor::from(config),
+            handshake_timeout: Duration::from_secs(10),
+            max_handshakes: 1024,
+            handshakes: FuturesUnordered::new(),
+        }
+    }
+
+    /// Maximum time a client has to complete its handshake. Defaults to 10 seconds.
+    pub fn handshake_timeout(mut self, timeout: Duration) -> Self {
+        self.handshake_timeout = timeout;
+        self
+    }
+
+    /// Maximum number of handshakes in progress at once. Defaults to 1024.
+    ///
+    /// # Panics
+    /// Panics if `max` is zero.
+    pub fn max_handshakes(mut self, max: usize) -> Self {
+        assert!(max > 0, "at least one handshake must be allowed");
+        self.max_handshakes = max;
+        self
+    }
+}
+
+impl<I> Accept for TlsIncoming<I>
+where
+    I: Accept,
+    I::Conn: AsyncRead + AsyncWrite + Unpin,
+{
+    type Conn = TlsStream<I::Conn>;
+    type Error = I::Error;
+
+    fn poll_accept(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Self::Conn, Self::Error>>> {
+        let mut this = self.project();
+
+        loop {
+            // Connections are left in the incoming stream while at the limit. A completed handshake
+            // wakes the task up, making room for them.
+            while !*this.incoming_done && this.handshakes.len() < *this.max_handshakes {
+                match this.incoming.as_mut().poll_accept(cx) {
+                    Poll::Ready(Some(Ok(conn))) => this.handshakes.push(tokio::time::timeout(
+                        *this.handshake_timeout,
+                        this.acceptor.accept(conn),
+                    )),
+                    Poll::Ready(Some(Err(err))) => return Poll::Ready(Some(Err(err))),
+                    Poll::Ready(None) => *this.incoming_done = true,
+                    Poll::Pending => break,
+                }
+            }
+
+            match this.handshakes.poll_next_unpin(cx) {
+                Poll::Ready(Some(Ok(Ok(stream)))) => return Poll::Ready(Some(Ok(stream))),
+                // The handshake failed or timed out, which made room for another connection
+                Poll::Ready(Some(_)) => continue,
+                Poll::Ready(None) if *this.incoming_done => return Poll::Ready(None),
+                Poll::Ready(None) | Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+impl<IO: Connected> Connected for TlsStream<IO> {
+    fn connection_info(&self) -> ConnectionInfo {
+        self.get_ref().0.connection_info()
+    }
+
+    fn tls_info(&self) -> Option<TlsInfo> {
+        Some(TlsInfo::new(self.get_ref().1))
+    }
+}
+
+/// Details of the TLS session a request arrived through
+///
+/// Available to handlers through [`RequestExt::tls_info`], or directly from the request's
+/// extensions.
+///
+/// [`RequestExt::tls_info`]: crate::request::RequestExt::tls_info
+#[derive(Debug, Clone)]
+pub struct TlsInfo {
+    inner: Arc<TlsInfoInner>,
+}
+
+#[derive(Debug)]
+struct TlsInfoInner {
+    server_name: Option<String>,
+    alpn_protocol: Option<Vec<u8>>,
+    peer_certificates: Option<Vec<CertificateDer<'static>>>,
+    protocol_version: Option<ProtocolVersion>,
+}
+
+impl TlsInfo {
+    fn new(conn: &ServerConnection) -> Self {
+        Self {
+            inner: Arc::new(TlsInfoInner {
+                server_name: conn.server_name().map(str::to_owned),
+                alpn_protocol: conn.alpn_protocol().map(<[u8]>::to_vec),
+                peer_certificates: conn.peer_certificates().map(<[_]>::to_vec),
+                protocol_version: conn.protocol_version(),
+            }),
+        }
+    }
+
+    /// Server name requested by the client through SNI
+    pub fn server_name(&self) -> Option<&str> {
+        self.inner.server_name.as_deref()
+    }
+
+    /// Protocol negotiated through ALPN (eg. `h2`)
+    pub fn alpn_protocol(&self) -> Option<&[u8]> {
+        self.inner.alpn_protocol.as_deref()
+    }
+
+    /// Certificate chain presented by the client, starting with its own certificate. Only
+    /// available when the server is configured to verify client certificates.
+    pub fn peer_certificates(&self) -> Option<&[CertificateDer<'static>]> {
+        self.inner.peer_certificates.as_deref()
+    }
+
+    pub fn protocol_version(&self) -> Option<ProtocolVersion> {
+        self.inner.protocol_version
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum TlsConfigError {
+    /// A PEM file is malformed
+    #[error("failed to read pem: {0}")]
+    Pem(#[from] io::Error),
+
+    /// A PEM file expected to contain certificates has none
+    #[error("no certificates found")]
+    NoCertificates,
+
+    /// A PEM file expected to contain a private key has none
+    #[error("no private key found")]
+    NoPrivateKey,
+
+    /// Rustls rejected the configuration (eg. the private key doesn't match the certificate)
+    #[error(transparent)]
+    Rustls(#[from] rustls::Error),
+}
+
+/// Server configuration for a PEM-encoded certificate chain and private key, without client
+/// authentication
+///
+/// For mutual TLS, build the configuration with a client certificate verifier instead, using
+/// [`load_certs`] and [`load_private_key`].
+pub fn server_config(
+    cert_chain_pem: &[u8],
+    key_pem: &[u8],
+) -> Result<ServerConfig, TlsConfigError> {
+    let config = ServerConfig::builder_with_provider(ring::default_provider().into())
+        .with_safe_default_protocol_versions()?
+        .with_no_client_auth()
+        .with_single_cert(load_certs(cert_chain_pem)?, load_private_key(key_pem)?)?;
+    Ok(config)
+}
+
+/// Parse every certificate of a PEM file
+pub fn load_certs(pem: &[u8]) -> Result<Vec<CertificateDer<'static>>, TlsConfigError> {
+    let certs = rustls_pemfile::certs(&mut &*pem).collect::<Result<Vec<_>, _>>()?;
+    match certs.is_empty() {
+        true => Err(TlsConfigError::NoCertificates),
+        false => Ok(certs),
+    }
+}
+
+/// Parse the first private key of a PEM file
+pub fn load_private_key(pem: &[u8]) -> Result<PrivateKeyDer<'static>, TlsConfigError> {
+    rustls_pemfile::private_key(&mut &*pem)?.ok_or(TlsConfigError::NoPrivateKey)
+}
@@ -0,0 +1,78 @@
+use hyper::StatusCode;
+use routerman::{
+    method::get,
+    request::{Request, RequestExt},
+    router::Router,
+    test::TestClient,
+};
+
+#[tokio::test]
+#[allow(deprecated)]
+async fn remote_address_of_tcp_connections() {
+    let router: Router = Router::builder()
+        .route(
+            "/",
+            get(|req: Request| async move { req.remote_address().to_string() }),
+        )
+        .build();
+    let client = TestClient::new(router).remote_addr("203.0.113.7:4000".parse().unwrap());
+
+    let res = client.get("/").send().await;
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(res.text().await, "203.0.113.7:4000");
+}
+
+#[cfg(all(unix, feature = "tokio"))]
+#[tokio::test]
+async fn unix_peer_credentials() {
+    use hyper::{server::accept, Server};
+    use routerman::connection::ConnectionInfo;
+    use std::task::Poll;
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::{UnixListener, UnixStream},
+    };
+
+    let router: Router = Router::builder()
+        .route(
+            "/",
+            get(|req: Request| async move {
+                let ConnectionInfo::Unix(peer) = req.connection_info() else {
+                    panic!("not a unix connection: {:?}", req.connection_info());
+                };
+                assert_eq!(peer.path, None);
+                let credentials = peer.credentials.unwrap();
+                format!("{} {}", credentials.uid, credentials.pid.unwrap())
+            }),
+        )
+        .build();
+
+    let dir = std::env::temp_dir().join(format!("routerman-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("unix_peer_credentials.sock");
+    let _ = std::fs::remove_file(&path);
+
+    let listener = UnixListener::bind(&path).unwrap();
+    let incoming = accept::poll_fn(move |cx| match listener.poll_accept(cx) {
+        Poll::Ready(res) => Poll::Ready(Some(res.map(|(stream, _)| stream))),
+        Poll::Pending => Poll::Pending,
+    });
+    tokio::spawn(Server::builder(incoming).serve(router));
+
+    let mut stream = UnixStream::connect(&path).await.unwrap();
+    let expected = stream.peer_cred().unwrap();
+    stream
+        .write_all(b"GET / HTTP/1.1\r\nhost: localhost\r\nconnection: close\r\n\r\n")
+        .await
+        .unwrap();
+    let mut res = String::new();
+    stream.read_to_string(&mut res).await.unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert!(res.starts_with("HTTP/1.1 200 OK"), "{}", res);
+    assert!(
+        res.ends_with(&format!("{} {}", expected.uid(), std::process::id())),
+        "{}",
+        res
+    );
+}
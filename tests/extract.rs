@@ -0,0 +1,194 @@
+#![cfg(all(feature = "json", feature = "query", feature = "path"))]
+
+use hyper::{body::Bytes, StatusCode};
+use routerman::{
+    json::Json,
+    method::{get, post},
+    request::{path::Path, query::Query, MatchedPath, Request, RequestExt},
+    router::Router,
+    test::TestClient,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+#[derive(Debug, Deserialize, Serialize, PartialEq)]
+struct Post {
+    user: String,
+    id: u32,
+}
+
+#[derive(Deserialize)]
+struct Pagination {
+    page: u32,
+    per_page: Option<u32>,
+}
+
+#[tokio::test]
+async fn path() {
+    let router: Router = Router::builder()
+        .route(
+            "/users/:user/posts/:id",
+            get(|Path(post): Path<Post>| async move { format!("{} {}", post.user, post.id) }),
+        )
+        .route(
+            "/users/:user/files/*path",
+            get(|Path((user, path)): Path<(String, String)>| async move {
+                format!("{} {}", user, path)
+            }),
+        )
+        .route(
+            "/posts/:id",
+            get(|Path(id): Path<u32>| async move { id.to_string() }),
+        )
+        .build();
+    let client = TestClient::new(router);
+
+    let res = client.get("/users/fer%20ris/posts/7").send().await;
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(res.text().await, "fer ris 7");
+
+    let res = client.get("/users/ferris/files/a/b%2Fc.txt").send().await;
+    assert_eq!(res.text().await, "ferris a/b/c.txt");
+
+    let res = client.get("/posts/seven").send().await;
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+    assert!(res.text().await.contains("`id`"));
+}
+
+#[tokio::test]
+async fn query() {
+    let router: Router = Router::builder()
+        .route(
+            "/posts",
+            get(|Query(page): Query<Pagination>| async move {
+                format!("{} {:?}", page.page, page.per_page)
+            }),
+        )
+        .build();
+    let client = TestClient::new(router);
+
+    let res = client.get("/posts?page=2&per_page=50").send().await;
+    assert_eq!(res.text().await, "2 Some(50)");
+
+    let res = client.get("/posts?page=3").send().await;
+    assert_eq!(res.text().await, "3 None");
+
+    let res = client.get("/posts").send().await;
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+
+    let res = client.get("/posts?page=last").send().await;
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn json() {
+    let router: Router = Router::builder()
+        .route(
+            "/posts",
+            post(|Json(post): Json<Post>| async move { Json(post) }),
+        )
+        .build();
+    let client = TestClient::new(router);
+
+    let post = Post {
+        user: "ferris".into(),
+        id: 7,
+    };
+    let res = client.post("/posts").json(&post).send().await;
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(res.header("content-type"), Some("application/json"));
+    assert_eq!(res.json::<Post>().await, post);
+
+    let res = client
+        .post("/posts")
+        .json(&json!({ "user": "ferris" }))
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+
+    let res = client.post("/posts").body("{").send().await;
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+}
+
+#[cfg(feature = "form")]
+#[tokio::test]
+async fn form() {
+    use routerman::form::Form;
+
+    let router: Router = Router::builder()
+        .route(
+            "/posts",
+            post(|Form(post): Form<Post>| async move { Form(post) }),
+        )
+        .build();
+    let client = TestClient::new(router);
+
+    let post = Post {
+        user: "fer ris".into(),
+        id: 7,
+    };
+    let res = client.post("/posts").form(&post).send().await;
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(
+        res.header("content-type"),
+        Some("application/x-www-form-urlencoded")
+    );
+    assert_eq!(res.text().await, "user=fer+ris&id=7");
+
+    let res = client.post("/posts").body("user=ferris").send().await;
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn extractors_combine() {
+    let router: Router = Router::builder()
+        .route(
+            "/users/:user/posts",
+            post(
+                |Path(user): Path<String>, Query(page): Query<Pagination>, body: Bytes| async move {
+                    format!("{} {} {}", user, page.page, body.len())
+                },
+            ),
+        )
+        .build();
+    let client = TestClient::new(router);
+
+    let res = client
+        .post("/users/ferris/posts?page=2")
+        .body("hello")
+        .send()
+        .await;
+    assert_eq!(res.text().await, "ferris 2 5");
+
+    // Any failing extractor rejects the request before the body is read
+    let res = client
+        .post("/users/ferris/posts")
+        .body("hello")
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn request_ext() {
+    let users = Router::builder().route_named(
+        "user",
+        "/:id",
+        get(|req: Request| async move {
+            assert_eq!(
+                req.matched_path(),
+                &MatchedPath::Route("/api/users/:id".into())
+            );
+            assert_eq!(req.nest_prefix(), Some("/api/users"));
+            assert_eq!(req.relative_path(), "/7");
+            assert_eq!(req.params().get("id"), Some("7"));
+            req.url_for("user", &[("id", "8")]).unwrap()
+        }),
+    );
+    let router: Router = Router::builder().nest("/api/users", users).build();
+    let client = TestClient::new(router);
+
+    let res = client.get("/api/users/7").send().await;
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(res.text().await, "/api/users/8");
+}
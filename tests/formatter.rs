@@ -0,0 +1,155 @@
+#![cfg(feature = "query")]
+
+use hyper::{header::HeaderValue, StatusCode, Uri};
+use routerman::{
+    method::get,
+    request::{query::Query, InvalidParamEncoding, Request},
+    response::{Formatter, Reply, Response},
+    router::Router,
+    test::TestClient,
+};
+use std::error::Error as StdError;
+
+/// Formatter replying with the name of the hook that was called and the path of the request
+#[derive(Clone, Default)]
+struct Tagged {
+    path: String,
+}
+
+impl Tagged {
+    fn tag(&self, status: StatusCode, hook: &str) -> Response {
+        (status, format!("{} {}", hook, self.path)).reply(self.clone())
+    }
+}
+
+impl Formatter for Tagged {
+    fn for_request(&self, req: &Request) -> Self {
+        Self {
+            path: req.uri().path().to_owned(),
+        }
+    }
+
+    fn not_found(&self, _req: &Request) -> Response {
+        self.tag(StatusCode::NOT_FOUND, "not_found")
+    }
+
+    fn method_not_allowed(&self, allow: &HeaderValue) -> Response {
+        let hook = format!("method_not_allowed({})", allow.to_str().unwrap());
+        self.tag(StatusCode::METHOD_NOT_ALLOWED, &hook)
+    }
+
+    fn options(&self, allow: &HeaderValue) -> Response {
+        let hook = format!("options({})", allow.to_str().unwrap());
+        self.tag(StatusCode::OK, &hook)
+    }
+
+    fn redirect(&self, location: &Uri) -> Response {
+        self.tag(StatusCode::OK, &format!("redirect({})", location))
+    }
+
+    fn bad_param(&self, _err: &InvalidParamEncoding) -> Response {
+        self.tag(StatusCode::BAD_REQUEST, "bad_param")
+    }
+
+    fn internal_error(&self, err: &dyn StdError) -> Response {
+        self.tag(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            &format!("internal_error({})", err),
+        )
+    }
+
+    fn format_error(&self, _err: &dyn StdError, status: StatusCode) -> Response {
+        self.tag(status, &format!("format_error({})", status.as_u16()))
+    }
+}
+
+/// Formatter only overriding `format_error`, which the other hooks fall back to
+#[derive(Clone, Default)]
+struct ErrorsOnly;
+
+impl Formatter for ErrorsOnly {
+    fn format_error(&self, err: &dyn StdError, status: StatusCode) -> Response {
+        (status, format!("error: {}", err)).reply(self.clone())
+    }
+}
+
+async fn fail() -> Result<&'static str, Box<dyn StdError + Send + Sync>> {
+    Err("database unavailable".into())
+}
+
+async fn page(Query(page): Query<u32>) -> String {
+    page.to_string()
+}
+
+fn client<Fmt: Formatter + Default>() -> TestClient<Fmt> {
+    TestClient::new(
+        Router::builder()
+            .route("/users", get(|| async { "users" }))
+            .route("/users/:id", get(|_req: Request| async { "user" }))
+            .route("/fail", get(fail))
+            .route("/page", get(page))
+            .build(),
+    )
+}
+
+async fn send(client: &TestClient<Tagged>, method: &str, uri: &str) -> (StatusCode, String) {
+    let res = client.request(method.parse().unwrap(), uri).send().await;
+    (res.status(), res.text().await)
+}
+
+#[tokio::test]
+async fn every_hook_can_be_overridden() {
+    let client = client::<Tagged>();
+
+    assert_eq!(
+        send(&client, "GET", "/missing").await,
+        (StatusCode::NOT_FOUND, "not_found /missing".into())
+    );
+    assert_eq!(
+        send(&client, "POST", "/users").await,
+        (
+            StatusCode::METHOD_NOT_ALLOWED,
+            "method_not_allowed(GET, HEAD, OPTIONS) /users".into()
+        )
+    );
+    assert_eq!(
+        send(&client, "OPTIONS", "/users").await,
+        (StatusCode::OK, "options(GET, HEAD, OPTIONS) /users".into())
+    );
+    assert_eq!(
+        send(&client, "GET", "/users/").await,
+        (StatusCode::OK, "redirect(/users) /users/".into())
+    );
+    assert_eq!(
+        send(&client, "GET", "/users/%FF").await,
+        (StatusCode::BAD_REQUEST, "bad_param /users/%FF".into())
+    );
+    assert_eq!(
+        send(&client, "GET", "/fail").await,
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "internal_error(database unavailable) /fail".into()
+        )
+    );
+    assert_eq!(
+        send(&client, "GET", "/page?x").await,
+        (StatusCode::BAD_REQUEST, "format_error(400) /page".into())
+    );
+}
+
+#[tokio::test]
+async fn internal_errors_default_to_format_error() {
+    let client = client::<ErrorsOnly>();
+
+    let res = client.get("/fail").send().await;
+    assert_eq!(res.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    assert_eq!(res.text().await, "error: database unavailable");
+
+    let res = client.get("/missing").send().await;
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+    assert_eq!(res.text().await, "");
+
+    let res = client.options("/users").send().await;
+    assert_eq!(res.status(), StatusCode::NO_CONTENT);
+    assert_eq!(res.header("allow"), Some("GET, HEAD, OPTIONS"));
+}
@@ -0,0 +1,170 @@
+#![cfg(all(feature = "json", feature = "query"))]
+
+use hyper::{Body, StatusCode};
+use routerman::{
+    method::get,
+    problem::ProblemJsonFormatter,
+    request::{query::Query, Request},
+    response::{Formatter, Reply, Response},
+    route::{Route, RouteService},
+    router::{Router, RouterBuilder},
+    test::TestClient,
+};
+use serde::Deserialize;
+use std::{convert::Infallible, error::Error as StdError, time::Duration};
+use tower::{layer::layer_fn, service_fn, Service, ServiceExt};
+
+/// Layer that replaces the request with a new one, dropping its extensions, and tags the response
+fn rebuild_request<Fmt>() -> impl tower::Layer<
+    RouteService<Fmt>,
+    Service = impl Service<Request, Response = Response, Error = Infallible, Future = impl Send> + Clone,
+> + Clone
+where
+    Fmt: Formatter,
+{
+    layer_fn(|inner: RouteService<Fmt>| {
+        service_fn(move |req: Request| {
+            let inner = inner.clone();
+            async move {
+                let mut rebuilt = Request::new(Body::empty());
+                *rebuilt.uri_mut() = req.uri().clone();
+                let mut res = inner.oneshot(rebuilt).await?;
+                res.headers_mut()
+                    .insert("x-layered", "true".parse().unwrap());
+                Ok::<_, Infallible>(res)
+            }
+        })
+    })
+}
+
+#[tokio::test]
+async fn layer_can_replace_request() {
+    #[derive(Deserialize)]
+    struct Page {
+        page: u32,
+    }
+
+    let router = RouterBuilder::<ProblemJsonFormatter>::new()
+        .route(
+            "/items",
+            get(|Query(page): Query<Page>| async move { page.page.to_string() }),
+        )
+        .route(
+            "/users",
+            get(|Query(page): Query<Page>| async move { page.page.to_string() }),
+        )
+        .layer(rebuild_request())
+        .build();
+    let client = TestClient::new(router);
+
+    let res = client.get("/items?page=7").send().await;
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(res.text().await, "7");
+
+    // Rejections are formatted for the request that caused them
+    for uri in ["/items", "/users"] {
+        let res = client.get(&format!("{uri}?page=first")).send().await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(res.header("x-layered"), Some("true"));
+        let problem = res.json::<serde_json::Value>().await;
+        assert_eq!(problem["instance"], uri);
+    }
+}
+
+/// Formatter replying with the name it was built with, followed by the paths it was specialized for
+#[derive(Clone)]
+struct Trail(String);
+
+impl Formatter for Trail {
+    fn for_request(&self, req: &Request) -> Self {
+        Self(format!("{} {}", self.0, req.uri().path()))
+    }
+
+    fn format_error(&self, _err: &dyn StdError, status: StatusCode) -> Response {
+        (status, self.0.clone()).reply(self.clone())
+    }
+}
+
+#[tokio::test]
+async fn layered_routes_use_the_router_formatter() {
+    let route = Route::<Trail>::new(get(|| async {
+        Err::<&str, Box<dyn StdError + Send + Sync>>("failed".into())
+    }))
+    .layer(rebuild_request());
+
+    // The same route is built into routers with different formatters, each specialized for every
+    // request separately
+    for name in ["a", "b"] {
+        let router = RouterBuilder::new()
+            .route("/x", route.clone())
+            .route("/y", route.clone())
+            .build_with(Trail(name.to_owned()));
+        let client = TestClient::new(router);
+
+        for path in ["/x", "/y", "/x"] {
+            let res = client.get(path).send().await;
+            assert_eq!(res.status(), StatusCode::INTERNAL_SERVER_ERROR);
+            assert_eq!(res.text().await, format!("{name} {path}"));
+        }
+    }
+}
+
+#[tokio::test]
+async fn layer_only_wraps_earlier_routes() {
+    let router: Router = Router::builder()
+        .route("/before", get(|| async { "" }))
+        .nest(
+            "/nested",
+            Router::builder().route("/", get(|| async { "" })),
+        )
+        .layer(rebuild_request())
+        .route("/after", get(|| async { "" }))
+        .build();
+    let client = TestClient::new(router);
+
+    for (uri, layered) in [("/before", true), ("/nested", true), ("/after", false)] {
+        let res = client.get(uri).send().await;
+        assert_eq!(res.header("x-layered").is_some(), layered, "{uri}");
+    }
+}
+
+#[tokio::test]
+async fn timeout_replies_gateway_timeout() {
+    use tower::timeout::TimeoutLayer;
+
+    let router: Router = Router::builder()
+        .route(
+            "/slow",
+            get(|| async {
+                tokio::time::sleep(Duration::from_secs(5)).await;
+                "Done!"
+            }),
+        )
+        .layer(TimeoutLayer::new(Duration::from_millis(10)))
+        .build();
+
+    let res = TestClient::new(router).get("/slow").send().await;
+    assert_eq!(res.status(), StatusCode::GATEWAY_TIMEOUT);
+}
+
+#[tokio::test]
+async fn concurrency_limit_is_shared() {
+    use tower::limit::ConcurrencyLimitLayer;
+
+    let router: Router = Router::builder()
+        .route(
+            "/",
+            get(|| async {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                "Done!"
+            }),
+        )
+        .layer(ConcurrencyLimitLayer::new(1))
+        .build();
+    let client = TestClient::new(router);
+
+    let start = std::time::Instant::now();
+    let (a, b) = tokio::join!(client.get("/").send(), client.get("/").send());
+    assert_eq!((a.status(), b.status()), (StatusCode::OK, StatusCode::OK));
+    assert!(start.elapsed() >= Duration::from_millis(100));
+}
@@ -0,0 +1,117 @@
+use hyper::{header, Body, Response, StatusCode};
+use routerman::{
+    method::{get, head, options, post},
+    route::Route,
+    router::Router,
+    test::TestClient,
+};
+
+#[tokio::test]
+async fn head_is_served_by_get() {
+    let router: Router = Router::builder()
+        .route("/", get(|| async { "Hello, World!" }))
+        .build();
+    let client = TestClient::new(router);
+
+    let res = client.head("/").send().await;
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(res.header(header::CONTENT_LENGTH), Some("13"));
+    assert_eq!(res.header(header::CONTENT_TYPE), Some("text/plain"));
+    assert!(res.bytes().await.is_empty());
+}
+
+#[tokio::test]
+async fn head_keeps_explicit_content_length() {
+    let router: Router = Router::builder()
+        .route(
+            "/",
+            get(|| async {
+                Response::builder()
+                    .header(header::CONTENT_LENGTH, "1024")
+                    .body(Body::wrap_stream(futures_util::stream::empty::<
+                        Result<Vec<u8>, std::io::Error>,
+                    >()))
+                    .unwrap()
+            }),
+        )
+        .build();
+    let client = TestClient::new(router);
+
+    let res = client.head("/").send().await;
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(res.header(header::CONTENT_LENGTH), Some("1024"));
+    assert!(res.bytes().await.is_empty());
+}
+
+#[tokio::test]
+async fn explicit_head_takes_priority() {
+    let router: Router = Router::builder()
+        .route(
+            "/",
+            get(|| async { "Hello, World!" })
+                | head(|| async { (StatusCode::NO_CONTENT, [("x-handler", "head")]) }),
+        )
+        .build();
+    let client = TestClient::new(router);
+
+    let res = client.head("/").send().await;
+    assert_eq!(res.status(), StatusCode::NO_CONTENT);
+    assert_eq!(res.header("x-handler"), Some("head"));
+
+    let res = client.get("/").send().await;
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(res.text().await, "Hello, World!");
+}
+
+#[tokio::test]
+async fn options_lists_allowed_methods() {
+    let router: Router = Router::builder()
+        .route("/", get(|| async { "get" }) | post(|| async { "post" }))
+        .build();
+    let client = TestClient::new(router);
+
+    let res = client.options("/").send().await;
+    assert_eq!(res.status(), StatusCode::NO_CONTENT);
+    assert_eq!(res.header(header::ALLOW), Some("GET, HEAD, OPTIONS, POST"));
+    assert!(res.bytes().await.is_empty());
+
+    let res = client.put("/").send().await;
+    assert_eq!(res.status(), StatusCode::METHOD_NOT_ALLOWED);
+    assert_eq!(res.header(header::ALLOW), Some("GET, HEAD, OPTIONS, POST"));
+}
+
+#[tokio::test]
+async fn explicit_options_takes_priority() {
+    let router: Router = Router::builder()
+        .route(
+            "/",
+            get(|| async { "get" })
+                | options(|| async { (StatusCode::OK, [("x-handler", "options")]) }),
+        )
+        .build();
+    let client = TestClient::new(router);
+
+    let res = client.options("/").send().await;
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(res.header("x-handler"), Some("options"));
+
+    let res = client.delete("/").send().await;
+    assert_eq!(res.status(), StatusCode::METHOD_NOT_ALLOWED);
+    assert_eq!(res.header(header::ALLOW), Some("GET, HEAD, OPTIONS"));
+}
+
+#[tokio::test]
+async fn fallback_handles_options() {
+    let router: Router = Router::builder()
+        .route(
+            "/",
+            get(|| async { "get" }).fallback(Route::new(|| async { "fallback" })),
+        )
+        .build();
+    let client = TestClient::new(router);
+
+    let res = client.options("/").send().await;
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(res.header(header::ALLOW), None);
+    assert_eq!(res.text().await, "fallback");
+}
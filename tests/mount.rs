@@ -0,0 +1,61 @@
+use hyper::StatusCode;
+use routerman::{
+    method::get,
+    request::{path::Path, Request, RequestExt},
+    route::Route,
+    router::Router,
+    test::TestClient,
+};
+
+/// Router describing the path and params it sees
+fn inner() -> Router {
+    Router::builder()
+        .route("/", get(|req: Request| async move { req.uri().to_string() }))
+        .route(
+            "/users/:id",
+            get(|Path(id): Path<String>, req: Request| async move {
+                format!("{} {}", req.uri(), id)
+            }),
+        )
+        .default_route(|req: Request| async move {
+            format!("default {} {:?}", req.uri(), req.nest_prefix())
+        })
+        .build()
+}
+
+#[tokio::test]
+async fn mounted_router_matches_relative_path() {
+    let router: Router = Router::builder()
+        .route("/legacy/:version/*path", Route::from_service(inner()))
+        .route("/exact", Route::from_service(inner()))
+        .nest(
+            "/v2",
+            Router::builder().default_route(Route::from_service(inner())),
+        )
+        .default_route(Route::from_service(inner()))
+        .build();
+    let client = TestClient::new(router);
+
+    for (uri, body) in [
+        ("/legacy/1/users/42?full=true", "/users/42?full=true 42"),
+        ("/legacy/1/unknown", "default /unknown None"),
+        ("/exact?q=1", "/?q=1"),
+        ("/v2", "/"),
+        ("/v2/users/7", "/users/7 7"),
+        ("/users/3", "/users/3 3"),
+    ] {
+        let res = client.get(uri).send().await;
+        assert_eq!(res.status(), StatusCode::OK, "{uri}");
+        assert_eq!(res.text().await, body, "{uri}");
+    }
+}
+
+#[tokio::test]
+async fn root_catch_all_keeps_path() {
+    let router: Router = Router::builder()
+        .route("/*path", Route::from_service(inner()))
+        .build();
+
+    let res = TestClient::new(router).get("/users/3").send().await;
+    assert_eq!(res.text().await, "/users/3 3");
+}
@@ -0,0 +1,118 @@
+use hyper::{header, StatusCode};
+use routerman::{
+    method::get,
+    request::{MatchedPath, Request, RequestExt},
+    router::Router,
+    test::TestClient,
+};
+
+/// Describe how a request was routed
+async fn describe(req: Request) -> String {
+    let matched = match req.matched_path() {
+        MatchedPath::Route(path) => path.to_string(),
+        MatchedPath::Default(prefix) => format!("default {}", prefix.as_deref().unwrap_or("")),
+    };
+    format!(
+        "{matched}|{}|{}",
+        req.nest_prefix().unwrap_or(""),
+        req.relative_path()
+    )
+}
+
+fn client() -> TestClient {
+    let users = Router::builder()
+        .route("/", get(describe))
+        .route("/:id", get(describe))
+        .route("/:id/posts/", get(describe))
+        .default_route(describe);
+    let v1 = Router::builder()
+        .route("/health", get(describe))
+        .nest("/users", users)
+        .default_route(describe);
+    let router: Router = Router::builder()
+        .nest("/api/v1/", v1)
+        .nest("/static", Router::builder().route("/*path", get(describe)))
+        .default_route(describe)
+        .build();
+    TestClient::new(router)
+}
+
+async fn get_text(client: &TestClient, uri: &str) -> String {
+    let res = client.get(uri).send().await;
+    assert_eq!(res.status(), StatusCode::OK, "{uri}");
+    res.text().await
+}
+
+#[tokio::test]
+async fn routes_are_prefixed() {
+    let client = client();
+    assert_eq!(
+        get_text(&client, "/api/v1/health").await,
+        "/api/v1/health|/api/v1|/health"
+    );
+    assert_eq!(
+        get_text(&client, "/api/v1/users").await,
+        "/api/v1/users|/api/v1/users|/"
+    );
+    assert_eq!(
+        get_text(&client, "/api/v1/users/42").await,
+        "/api/v1/users/:id|/api/v1/users|/42"
+    );
+    assert_eq!(
+        get_text(&client, "/static/css/site.css").await,
+        "/static/*path|/static|/css/site.css"
+    );
+    assert_eq!(get_text(&client, "/health").await, "default ||/health");
+}
+
+#[tokio::test]
+async fn innermost_default_applies() {
+    let client = client();
+    assert_eq!(
+        get_text(&client, "/api/v1/users/42/comments").await,
+        "default /api/v1/users|/api/v1/users|/42/comments"
+    );
+    assert_eq!(
+        get_text(&client, "/api/v1/posts").await,
+        "default /api/v1|/api/v1|/posts"
+    );
+    assert_eq!(
+        get_text(&client, "/api/v1").await,
+        "default /api/v1|/api/v1|/"
+    );
+    assert_eq!(get_text(&client, "/api/v2").await, "default ||/api/v2");
+    assert_eq!(
+        get_text(&client, "/api/v1users").await,
+        "default ||/api/v1users"
+    );
+}
+
+#[tokio::test]
+async fn nested_default_handles_trailing_slash() {
+    let client = client();
+    assert_eq!(
+        get_text(&client, "/api/v1/users/").await,
+        "default /api/v1/users|/api/v1/users|/"
+    );
+    assert_eq!(
+        get_text(&client, "/api/v1/users/42/posts").await,
+        "default /api/v1/users|/api/v1/users|/42/posts"
+    );
+    assert_eq!(
+        get_text(&client, "/api/v1/health/").await,
+        "default /api/v1|/api/v1|/health/"
+    );
+}
+
+#[tokio::test]
+async fn trailing_slash_redirects_without_nested_default() {
+    let router: Router = Router::builder()
+        .nest("/api", Router::builder().route("/users", get(describe)))
+        .default_route(describe)
+        .build();
+    let client = TestClient::new(router);
+
+    let res = client.get("/api/users/?page=2").send().await;
+    assert!(res.status().is_redirection());
+    assert_eq!(res.header(header::LOCATION), Some("/api/users?page=2"));
+}
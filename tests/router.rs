@@ -0,0 +1,123 @@
+use routerman::{
+    method::get,
+    response::DefaultFormatter,
+    router::{BuildErrorKind, ProxyHeader, Router, RouterBuilder, UrlForError},
+};
+
+fn conflicts(builder: routerman::router::RouterBuilder) -> Vec<(String, String)> {
+    let err = match builder.try_build() {
+        Ok(_) => panic!("expected the router to fail to build"),
+        Err(err) => err,
+    };
+    err.errors
+        .into_iter()
+        .map(|err| match err {
+            BuildErrorKind::Conflict { path, with } => (path, with),
+            err => panic!("unexpected error: {err}"),
+        })
+        .collect()
+}
+
+#[test]
+fn reports_conflicts() {
+    let builder = Router::builder()
+        .route("/users/:id", get(|| async { "" }))
+        .route("/users/:name", get(|| async { "" }))
+        .route("/users/:id/posts", get(|| async { "" }))
+        .route("/files/*path", get(|| async { "" }))
+        .route("/files/:name", get(|| async { "" }));
+
+    assert_eq!(
+        conflicts(builder),
+        [
+            ("/users/:name".to_owned(), "/users/:id".to_owned()),
+            ("/files/:name".to_owned(), "/files/*path".to_owned()),
+        ]
+    );
+}
+
+#[test]
+fn failed_route_does_not_cause_conflicts() {
+    let err = Router::<DefaultFormatter>::builder()
+        .route("/x/:a/*b/c", get(|| async { "" }))
+        .route("/x/:other", get(|| async { "" }))
+        .try_build()
+        .err()
+        .unwrap();
+
+    assert_eq!(err.errors.len(), 1);
+    assert!(matches!(
+        &err.errors[0],
+        BuildErrorKind::InvalidPath { path, .. } if path == "/x/:a/*b/c"
+    ));
+}
+
+#[test]
+fn rejects_nested_trusted_proxies() {
+    let child = || -> RouterBuilder {
+        Router::builder()
+            .route("/", get(|| async { "" }))
+            .trusted_proxy("10.0.0.0/8".parse().unwrap(), ProxyHeader::XForwardedFor)
+    };
+
+    for builder in [
+        Router::builder().merge(child()),
+        Router::builder().nest("/child", child()),
+    ] {
+        let err = builder.try_build().err().unwrap();
+        assert_eq!(err.errors.len(), 1);
+        assert!(matches!(err.errors[0], BuildErrorKind::NestedTrustedProxy));
+    }
+}
+
+#[test]
+fn url_for_encodes_params() {
+    let router: Router = Router::builder()
+        .route_named("user", "/users/:id", get(|| async { "" }))
+        .route_named("file", "/users/:id/files/*path", get(|| async { "" }))
+        .route_named("version", "/v:version/", get(|| async { "" }))
+        .build();
+
+    assert_eq!(
+        router.url_for("user", &[("id", "42")]).unwrap(),
+        "/users/42"
+    );
+    assert_eq!(
+        router.url_for("user", &[("id", "a b/c?d#e%f")]).unwrap(),
+        "/users/a%20b%2Fc%3Fd%23e%25f"
+    );
+    assert_eq!(
+        router
+            .url_for("file", &[("path", "docs/a b.txt"), ("id", "é")])
+            .unwrap(),
+        "/users/%C3%A9/files/docs/a%20b.txt"
+    );
+    assert_eq!(
+        router.url_for("version", &[("version", "2")]).unwrap(),
+        "/v2/"
+    );
+}
+
+#[test]
+fn url_for_errors() {
+    let router: Router = Router::builder()
+        .route_named("file", "/users/:id/files/*path", get(|| async { "" }))
+        .build();
+
+    assert!(matches!(
+        router.url_for("unknown", &[]),
+        Err(UrlForError::UnknownRoute(name)) if name == "unknown"
+    ));
+    assert!(matches!(
+        router.url_for("file", &[("id", "1")]),
+        Err(UrlForError::MissingParam { name, param }) if name == "file" && param == "path"
+    ));
+    assert!(matches!(
+        router.url_for("file", &[("id", "1"), ("path", "a"), ("page", "2")]),
+        Err(UrlForError::ExtraParam { name, param }) if name == "file" && param == "page"
+    ));
+    assert!(matches!(
+        router.url_for("file", &[("id", "1"), ("path", "a"), ("id", "2")]),
+        Err(UrlForError::ExtraParam { param, .. }) if param == "id"
+    ));
+}
@@ -0,0 +1,217 @@
+#![cfg(feature = "server")]
+
+use hyper::{body::HttpBody, server::conn::AddrIncoming, Body, Client, StatusCode};
+use routerman::{method::get, router::Router, server::ShutdownReport};
+use std::{net::SocketAddr, time::Duration};
+use tokio::{
+    sync::{mpsc, oneshot},
+    task::JoinHandle,
+};
+
+/// Bind a random port, serving the router until `shutdown` is sent
+fn serve(
+    router: Router,
+    drain_timeout: Option<Duration>,
+) -> (
+    SocketAddr,
+    oneshot::Sender<()>,
+    JoinHandle<ShutdownReport>,
+    routerman::server::InFlight,
+) {
+    let incoming = AddrIncoming::bind(&([127, 0, 0, 1], 0).into()).unwrap();
+    let addr = incoming.local_addr();
+    let (shutdown, signal) = oneshot::channel::<()>();
+
+    let mut server = routerman::serve(router)
+        .bind_incoming(incoming)
+        .with_graceful_shutdown(async move {
+            let _ = signal.await;
+        });
+    if let Some(timeout) = drain_timeout {
+        server = server.drain_timeout(timeout);
+    }
+    let in_flight = server.in_flight();
+    let handle = tokio::spawn(async move { server.run().await.unwrap() });
+    (addr, shutdown, handle, in_flight)
+}
+
+#[tokio::test]
+async fn in_flight_request_finishes_during_drain() {
+    let (started_tx, mut started) = mpsc::channel::<()>(1);
+    let (finish, finish_rx) = mpsc::channel::<()>(1);
+    let finish_rx = std::sync::Arc::new(tokio::sync::Mutex::new(finish_rx));
+    let router: Router = Router::builder()
+        .route(
+            "/slow",
+            get(move || {
+                let started = started_tx.clone();
+                let finish = finish_rx.clone();
+                async move {
+                    started.send(()).await.unwrap();
+                    finish.lock().await.recv().await;
+                    "done"
+                }
+            }),
+        )
+        .build();
+    let (addr, shutdown, server, in_flight) = serve(router, Some(Duration::from_secs(5)));
+
+    let req = tokio::spawn(Client::new().get(format!("http://{}/slow", addr).parse().unwrap()));
+    started.recv().await.unwrap();
+    assert_eq!(in_flight.get(), 1);
+
+    // The server waits for the request once signalled
+    shutdown.send(()).unwrap();
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert!(!server.is_finished());
+
+    finish.send(()).await.unwrap();
+    let res = req.await.unwrap().unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(hyper::body::to_bytes(res).await.unwrap(), "done");
+
+    let report = server.await.unwrap();
+    assert!(!report.timed_out());
+    assert_eq!(report.in_flight(), 0);
+}
+
+#[tokio::test]
+async fn drain_timeout_aborts_connections() {
+    /// Notifies when the handler's future is dropped
+    struct Dropped(Option<oneshot::Sender<()>>);
+
+    impl Drop for Dropped {
+        fn drop(&mut self) {
+            let _ = self.0.take().unwrap().send(());
+        }
+    }
+
+    let (started_tx, mut started) = mpsc::channel::<()>(1);
+    let (dropped_tx, dropped) = oneshot::channel();
+    let dropped_tx = std::sync::Mutex::new(Some(dropped_tx));
+    let router: Router = Router::builder()
+        .route(
+            "/stuck",
+            get(move || {
+                let started = started_tx.clone();
+                let dropped = Dropped(dropped_tx.lock().unwrap().take());
+                async move {
+                    let _dropped = dropped;
+                    started.send(()).await.unwrap();
+                    futures_util::future::pending::<&str>().await
+                }
+            }),
+        )
+        .build();
+    let (addr, shutdown, server, _) = serve(router, Some(Duration::from_millis(100)));
+
+    let req = tokio::spawn(Client::new().get(format!("http://{}/stuck", addr).parse().unwrap()));
+    started.recv().await.unwrap();
+    shutdown.send(()).unwrap();
+
+    let report = server.await.unwrap();
+    assert!(report.timed_out());
+    assert_eq!(report.in_flight(), 1);
+
+    // The connection was aborted, dropping the handler and closing the socket
+    tokio::time::timeout(Duration::from_secs(5), dropped)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(req.await.unwrap().is_err());
+}
+
+#[tokio::test]
+async fn requests_are_in_flight_until_their_body_is_sent() {
+    let (senders_tx, mut senders) = mpsc::channel(1);
+    let router: Router = Router::builder()
+        .route(
+            "/stream",
+            get(move || {
+                let senders = senders_tx.clone();
+                async move {
+                    let (sender, body) = Body::channel();
+                    senders.send(sender).await.unwrap();
+                    body
+                }
+            }),
+        )
+        .build();
+    let (addr, _shutdown, _server, in_flight) = serve(router, None);
+
+    // The handler has returned, but the body is still being streamed
+    let res = Client::new()
+        .get(format!("http://{}/stream", addr).parse().unwrap())
+        .await
+        .unwrap();
+    let mut sender = senders.recv().await.unwrap();
+    assert_eq!(in_flight.get(), 1);
+
+    sender.send_data("hello".into()).await.unwrap();
+    drop(sender);
+    let mut body = res.into_body();
+    assert_eq!(body.data().await.unwrap().unwrap(), "hello");
+    assert!(body.data().await.is_none());
+
+    for _ in 0..100 {
+        if in_flight.get() == 0 {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    panic!("request still in flight after its body was sent");
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn serves_unix_sockets() {
+    use hyper::server::accept;
+    use routerman::request::{Request, RequestExt};
+    use std::task::Poll;
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::{UnixListener, UnixStream},
+    };
+
+    let router: Router = Router::builder()
+        .route(
+            "/",
+            get(|req: Request| async move {
+                format!("{}", req.connection_info().remote_addr().is_none())
+            }),
+        )
+        .build();
+
+    let dir = std::env::temp_dir().join(format!("routerman-server-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("serves_unix_sockets.sock");
+    let _ = std::fs::remove_file(&path);
+    let listener = UnixListener::bind(&path).unwrap();
+    let incoming = accept::poll_fn(move |cx| match listener.poll_accept(cx) {
+        Poll::Ready(res) => Poll::Ready(Some(res.map(|(stream, _)| stream))),
+        Poll::Pending => Poll::Pending,
+    });
+    let (shutdown, signal) = oneshot::channel::<()>();
+    let server = tokio::spawn(
+        routerman::serve(router)
+            .bind_incoming(incoming)
+            .with_graceful_shutdown(async move {
+                let _ = signal.await;
+            })
+            .run(),
+    );
+
+    let mut stream = UnixStream::connect(&path).await.unwrap();
+    stream
+        .write_all(b"GET / HTTP/1.1\r\nhost: localhost\r\nconnection: close\r\n\r\n")
+        .await
+        .unwrap();
+    let mut res = String::new();
+    stream.read_to_string(&mut res).await.unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert!(res.starts_with("HTTP/1.1 200 OK"), "{}", res);
+    assert!(res.ends_with("true"), "{}", res);
+
+    shutdown.send(()).unwrap();
+    assert!(!server.await.unwrap().unwrap().timed_out());
+}
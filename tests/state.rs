@@ -0,0 +1,123 @@
+use hyper::StatusCode;
+use routerman::{
+    method::get,
+    request::{state::State, Request, RequestExt},
+    response::DefaultFormatter,
+    router::{BuildErrorKind, Router},
+    test::TestClient,
+};
+
+#[derive(Clone)]
+struct Name(&'static str);
+
+async fn name(State(Name(name)): State<Name>) -> &'static str {
+    name
+}
+
+#[tokio::test]
+async fn state_is_scoped_to_subtree() {
+    let admin = Router::builder()
+        .route("/", get(name))
+        .default_route(name)
+        .with_state(Name("admin"));
+    let users = Router::builder()
+        .route("/users", get(name))
+        .with_state(Name("users"));
+    let router: Router = Router::builder()
+        .route("/", get(name))
+        .route(
+            "/count",
+            get(|req: Request| async move { req.state::<u32>().to_string() }),
+        )
+        .nest("/admin", admin)
+        .merge(users)
+        .with_state(Name("public"))
+        .with_state(42u32)
+        .build();
+    let client = TestClient::new(router);
+
+    for (uri, body) in [
+        ("/", "public"),
+        ("/count", "42"),
+        ("/admin", "admin"),
+        ("/admin/unknown", "admin"),
+        ("/users", "users"),
+    ] {
+        let res = client.get(uri).send().await;
+        assert_eq!(res.status(), StatusCode::OK, "{uri}");
+        assert_eq!(res.text().await, body, "{uri}");
+    }
+}
+
+#[tokio::test]
+async fn outer_state_reaches_nested_routes() {
+    let router: Router = Router::builder()
+        .nest("/api", Router::builder().route("/name", get(name)))
+        .with_state(Name("outer"))
+        .build();
+
+    let res = TestClient::new(router).get("/api/name").send().await;
+    assert_eq!(res.text().await, "outer");
+}
+
+#[test]
+fn nested_state_does_not_leak() {
+    let err = Router::<DefaultFormatter>::builder()
+        .route("/", get(name))
+        .nest(
+            "/admin",
+            Router::builder()
+                .route("/", get(name))
+                .with_state(Name("admin")),
+        )
+        .try_build()
+        .err()
+        .unwrap();
+
+    assert_eq!(err.errors.len(), 1);
+    assert!(matches!(
+        &err.errors[0],
+        BuildErrorKind::MissingState { route, state }
+            if route == "route `/`" && state.ends_with("::Name")
+    ));
+}
+
+#[test]
+fn missing_state_of_default_routes() {
+    let err = Router::<DefaultFormatter>::builder()
+        .nest("/api", Router::builder().default_route(name))
+        .default_route(|State(count): State<u32>| async move { count.to_string() })
+        .try_build()
+        .err()
+        .unwrap();
+
+    let routes = err
+        .errors
+        .iter()
+        .map(|err| match err {
+            BuildErrorKind::MissingState { route, .. } => route.as_str(),
+            err => panic!("unexpected error: {err}"),
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        routes,
+        ["the default route nested under `/api`", "the default route"]
+    );
+}
+
+#[test]
+fn duplicate_state() {
+    let err = Router::<DefaultFormatter>::builder()
+        .route("/", get(name))
+        .with_state(Name("first"))
+        .with_state(Name("second"))
+        .try_build()
+        .err()
+        .unwrap();
+
+    assert_eq!(err.errors.len(), 1);
+    assert!(matches!(
+        &err.errors[0],
+        BuildErrorKind::DuplicateState { state } if state.ends_with("::Name")
+    ));
+}
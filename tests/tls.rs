@@ -0,0 +1,215 @@
+#![cfg(feature = "tls")]
+
+use hyper::{server::conn::AddrIncoming, Server};
+use rcgen::{
+    BasicConstraints, CertificateParams, CertifiedKey, ExtendedKeyUsagePurpose, IsCa, KeyPair,
+};
+use routerman::{
+    method::get,
+    request::{Request, RequestExt},
+    router::Router,
+    tls::{
+        self,
+        rustls::{
+            self,
+            crypto::ring,
+            pki_types::{CertificateDer, PrivateKeyDer, PrivatePkcs8KeyDer, ServerName},
+            server::WebPkiClientVerifier,
+            ClientConfig, RootCertStore, ServerConfig,
+        },
+        TlsIncoming,
+    },
+};
+use std::{
+    net::SocketAddr,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+};
+use tokio_rustls::TlsConnector;
+
+/// Router describing the TLS session of each request
+fn router() -> Router {
+    Router::builder()
+        .route(
+            "/",
+            get(|req: Request| async move {
+                let tls = req.tls_info().unwrap();
+                let peer_certificates = tls
+                    .peer_certificates()
+                    .map(|certs| certs.iter().map(|cert| cert.len().to_string()))
+                    .into_iter()
+                    .flatten()
+                    .collect::<Vec<_>>();
+                format!(
+                    "{} {:?} [{}]",
+                    tls.server_name().unwrap_or("-"),
+                    tls.protocol_version().unwrap(),
+                    peer_certificates.join(",")
+                )
+            }),
+        )
+        .build()
+}
+
+/// Serve the router over TLS on a random port
+fn serve(incoming: impl FnOnce(AddrIncoming) -> TlsIncoming) -> SocketAddr {
+    let plain = AddrIncoming::bind(&([127, 0, 0, 1], 0).into()).unwrap();
+    let addr = plain.local_addr();
+    tokio::spawn(Server::builder(incoming(plain)).serve(router()));
+    addr
+}
+
+fn client_config(
+    server_cert: &CertifiedKey,
+    client_auth: Option<(Vec<CertificateDer<'static>>, PrivateKeyDer<'static>)>,
+) -> Arc<ClientConfig> {
+    let mut roots = RootCertStore::empty();
+    roots.add(server_cert.cert.der().clone()).unwrap();
+    let builder = ClientConfig::builder_with_provider(ring::default_provider().into())
+        .with_safe_default_protocol_versions()
+        .unwrap()
+        .with_root_certificates(roots);
+    let config = match client_auth {
+        Some((chain, key)) => builder.with_client_auth_cert(chain, key).unwrap(),
+        None => builder.with_no_client_auth(),
+    };
+    Arc::new(config)
+}
+
+/// Send a request through TLS and return the response's body
+async fn fetch(addr: SocketAddr, config: Arc<ClientConfig>) -> String {
+    let stream = TcpStream::connect(addr).await.unwrap();
+    let mut stream = TlsConnector::from(config)
+        .connect(ServerName::try_from("localhost").unwrap(), stream)
+        .await
+        .unwrap();
+    stream
+        .write_all(b"GET / HTTP/1.1\r\nhost: localhost\r\nconnection: close\r\n\r\n")
+        .await
+        .unwrap();
+    let mut res = String::new();
+    stream.read_to_string(&mut res).await.unwrap();
+    assert!(res.starts_with("HTTP/1.1 200 OK"), "{}", res);
+    res.split("\r\n\r\n").nth(1).unwrap().to_owned()
+}
+
+#[tokio::test]
+async fn tls_info() {
+    let cert = rcgen::generate_simple_self_signed(vec!["localhost".into()]).unwrap();
+    let config = tls::server_config(
+        cert.cert.pem().as_bytes(),
+        cert.key_pair.serialize_pem().as_bytes(),
+    )
+    .unwrap();
+    let addr = serve(|plain| TlsIncoming::new(plain, config.into()));
+
+    let res = fetch(addr, client_config(&cert, None)).await;
+    assert_eq!(res, "localhost TLSv1_3 []");
+}
+
+#[tokio::test]
+async fn client_certificates() {
+    let server_cert = rcgen::generate_simple_self_signed(vec!["localhost".into()]).unwrap();
+
+    let ca_key = KeyPair::generate().unwrap();
+    let mut ca_params = CertificateParams::new(Vec::<String>::new()).unwrap();
+    ca_params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+    let ca = ca_params.self_signed(&ca_key).unwrap();
+
+    let client_key = KeyPair::generate().unwrap();
+    let mut client_params = CertificateParams::new(vec!["client".into()]).unwrap();
+    client_params.extended_key_usages = vec![ExtendedKeyUsagePurpose::ClientAuth];
+    let client = client_params.signed_by(&client_key, &ca, &ca_key).unwrap();
+
+    // Only clients presenting a certificate issued by the CA are accepted
+    let mut client_roots = RootCertStore::empty();
+    client_roots.add(ca.der().clone()).unwrap();
+    let provider = Arc::new(ring::default_provider());
+    let verifier =
+        WebPkiClientVerifier::builder_with_provider(client_roots.into(), provider.clone())
+            .build()
+            .unwrap();
+    let config = ServerConfig::builder_with_provider(provider)
+        .with_safe_default_protocol_versions()
+        .unwrap()
+        .with_client_cert_verifier(verifier)
+        .with_single_cert(
+            tls::load_certs(server_cert.cert.pem().as_bytes()).unwrap(),
+            tls::load_private_key(server_cert.key_pair.serialize_pem().as_bytes()).unwrap(),
+        )
+        .unwrap();
+    let addr = serve(|plain| TlsIncoming::new(plain, config.into()));
+
+    let chain = vec![client.der().clone(), ca.der().clone()];
+    let key = PrivatePkcs8KeyDer::from(client_key.serialize_der()).into();
+    let res = fetch(addr, client_config(&server_cert, Some((chain, key)))).await;
+    assert_eq!(
+        res,
+        format!(
+            "localhost TLSv1_3 [{},{}]",
+            client.der().len(),
+            ca.der().len()
+        )
+    );
+
+    // Clients without a certificate fail the handshake
+    let stream = TcpStream::connect(addr).await.unwrap();
+    let mut stream = TlsConnector::from(client_config(&server_cert, None))
+        .connect(ServerName::try_from("localhost").unwrap(), stream)
+        .await
+        .unwrap();
+    stream.write_all(b"GET / HTTP/1.1\r\n\r\n").await.unwrap();
+    let err = stream.read_to_end(&mut Vec::new()).await.unwrap_err();
+    assert!(matches!(
+        err.get_ref()
+            .and_then(|err| err.downcast_ref::<rustls::Error>()),
+        Some(rustls::Error::AlertReceived(_))
+    ));
+}
+
+#[tokio::test]
+async fn stalled_handshakes_time_out() {
+    let cert = rcgen::generate_simple_self_signed(vec!["localhost".into()]).unwrap();
+    let config = tls::server_config(
+        cert.cert.pem().as_bytes(),
+        cert.key_pair.serialize_pem().as_bytes(),
+    )
+    .unwrap();
+    let addr = serve(|plain| {
+        TlsIncoming::new(plain, config.into()).handshake_timeout(Duration::from_millis(100))
+    });
+
+    // A client that never starts its handshake is disconnected
+    let mut stalled = TcpStream::connect(addr).await.unwrap();
+    let read = tokio::time::timeout(Duration::from_secs(5), stalled.read(&mut [0; 16])).await;
+    assert_eq!(read.unwrap().unwrap(), 0);
+}
+
+#[tokio::test]
+async fn handshakes_are_limited() {
+    let cert = rcgen::generate_simple_self_signed(vec!["localhost".into()]).unwrap();
+    let config = tls::server_config(
+        cert.cert.pem().as_bytes(),
+        cert.key_pair.serialize_pem().as_bytes(),
+    )
+    .unwrap();
+    let timeout = Duration::from_millis(300);
+    let addr = serve(|plain| {
+        TlsIncoming::new(plain, config.into())
+            .handshake_timeout(timeout)
+            .max_handshakes(1)
+    });
+
+    // The stalled client takes the only handshake slot until it times out
+    let _stalled = TcpStream::connect(addr).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    let start = Instant::now();
+    let res = fetch(addr, client_config(&cert, None)).await;
+    assert_eq!(res, "localhost TLSv1_3 []");
+    assert!(start.elapsed() >= timeout - Duration::from_millis(100));
+}